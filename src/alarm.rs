@@ -0,0 +1,208 @@
+//! Resources for configuring alarms, which start playback on a speaker at a given time
+//!
+//! ```rust
+//! # use rusty_sonos::alarm::{AlarmConfig, AlarmRecurrence};
+//! # use std::time::Duration;
+//! // wake up at 7:30 on weekdays, at volume 15
+//! let alarm = AlarmConfig::new("RINCON_000E58A0123401400", Duration::from_secs(7 * 3600 + 30 * 60))
+//!     .recurrence(AlarmRecurrence::Weekdays)
+//!     .volume(15);
+//! ```
+
+use std::{collections::HashSet, time::Duration};
+
+use crate::responses::PlayMode;
+
+/// The URI of the chime built into speakers, played by alarms with no other source
+pub const BUZZER_URI: &str = "x-rincon-buzzer:0";
+
+/// A day of the week
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Weekday {
+    /// Sunday
+    Sunday,
+    /// Monday
+    Monday,
+    /// Tuesday
+    Tuesday,
+    /// Wednesday
+    Wednesday,
+    /// Thursday
+    Thursday,
+    /// Friday
+    Friday,
+    /// Saturday
+    Saturday,
+}
+
+impl Weekday {
+    fn from_number(number: char) -> Option<Self> {
+        match number {
+            '0' => Some(Self::Sunday),
+            '1' => Some(Self::Monday),
+            '2' => Some(Self::Tuesday),
+            '3' => Some(Self::Wednesday),
+            '4' => Some(Self::Thursday),
+            '5' => Some(Self::Friday),
+            '6' => Some(Self::Saturday),
+            _ => None,
+        }
+    }
+
+    // speakers number days starting from Sunday
+    fn number(&self) -> u8 {
+        match self {
+            Self::Sunday => 0,
+            Self::Monday => 1,
+            Self::Tuesday => 2,
+            Self::Wednesday => 3,
+            Self::Thursday => 4,
+            Self::Friday => 5,
+            Self::Saturday => 6,
+        }
+    }
+}
+
+/// The days on which an alarm plays
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AlarmRecurrence {
+    /// The alarm plays once, then disables itself
+    Once,
+    /// The alarm plays every day
+    Daily,
+    /// The alarm plays Monday through Friday
+    Weekdays,
+    /// The alarm plays on Saturday and Sunday
+    Weekends,
+    /// The alarm plays on the given days
+    On(HashSet<Weekday>),
+}
+
+impl AlarmRecurrence {
+    pub(crate) fn new(recurrence_str: &str) -> Result<Self, String> {
+        match recurrence_str {
+            "ONCE" => Ok(Self::Once),
+            "DAILY" => Ok(Self::Daily),
+            "WEEKDAYS" => Ok(Self::Weekdays),
+            "WEEKENDS" => Ok(Self::Weekends),
+            _ => recurrence_str
+                .strip_prefix("ON_")
+                .and_then(|days| days.chars().map(Weekday::from_number).collect())
+                .map(Self::On)
+                .ok_or_else(|| String::from("Invalid alarm recurrence")),
+        }
+    }
+
+    pub(crate) fn to_recurrence_string(&self) -> String {
+        match self {
+            Self::Once => String::from("ONCE"),
+            Self::Daily => String::from("DAILY"),
+            Self::Weekdays => String::from("WEEKDAYS"),
+            Self::Weekends => String::from("WEEKENDS"),
+            Self::On(days) => {
+                let mut numbers = days.iter().map(Weekday::number).collect::<Vec<u8>>();
+                numbers.sort();
+
+                let days = numbers
+                    .iter()
+                    .map(u8::to_string)
+                    .collect::<Vec<String>>()
+                    .join("");
+
+                format!("ON_{}", days)
+            }
+        }
+    }
+}
+
+/// The settings for a new alarm
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlarmConfig {
+    /// The UUID of the speaker that plays the alarm
+    pub room_uuid: String,
+    /// The time of day at which the alarm starts, as time since midnight
+    pub start_time: Duration,
+    /// How long the alarm plays for (defaults to 1 hour)
+    pub duration: Duration,
+    /// The days on which the alarm plays (defaults to daily)
+    pub recurrence: AlarmRecurrence,
+    /// Whether the alarm is enabled (defaults to true)
+    pub enabled: bool,
+    /// The URI of the audio played by the alarm (defaults to the built-in chime, `BUZZER_URI`)
+    pub program_uri: String,
+    /// The DIDL-Lite metadata of the audio played by the alarm (defaults to none)
+    pub program_metadata: String,
+    /// The shuffle and repeat mode used while the alarm plays (defaults to normal)
+    pub play_mode: PlayMode,
+    /// The volume at which the alarm plays, from 0 to 100 (defaults to 20)
+    pub volume: u8,
+    /// Whether the alarm also plays on the other speakers grouped with the alarm's speaker (defaults to false)
+    pub include_linked_zones: bool,
+}
+
+impl AlarmConfig {
+    /// Creates the settings for a daily alarm that plays the built-in chime
+    ///
+    /// * `room_uuid` - the UUID of the speaker that plays the alarm
+    /// * `start_time` - the time of day at which the alarm starts, as time since midnight
+    pub fn new(room_uuid: &str, start_time: Duration) -> Self {
+        AlarmConfig {
+            room_uuid: room_uuid.to_owned(),
+            start_time,
+            duration: Duration::from_secs(3600),
+            recurrence: AlarmRecurrence::Daily,
+            enabled: true,
+            program_uri: BUZZER_URI.to_owned(),
+            program_metadata: String::new(),
+            play_mode: PlayMode::Normal,
+            volume: 20,
+            include_linked_zones: false,
+        }
+    }
+
+    /// Sets how long the alarm plays for
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    /// Sets the days on which the alarm plays
+    pub fn recurrence(mut self, recurrence: AlarmRecurrence) -> Self {
+        self.recurrence = recurrence;
+        self
+    }
+
+    /// Sets whether the alarm is enabled
+    pub fn enabled(mut self, enabled: bool) -> Self {
+        self.enabled = enabled;
+        self
+    }
+
+    /// Sets the URI of the audio played by the alarm, along with its DIDL-Lite metadata
+    pub fn program(mut self, uri: &str, metadata: &str) -> Self {
+        self.program_uri = uri.to_owned();
+        self.program_metadata = metadata.to_owned();
+        self
+    }
+
+    /// Sets the shuffle and repeat mode used while the alarm plays
+    pub fn play_mode(mut self, play_mode: PlayMode) -> Self {
+        self.play_mode = play_mode;
+        self
+    }
+
+    /// Sets the volume at which the alarm plays, from 0 to 100
+    pub fn volume(mut self, volume: u8) -> Self {
+        self.volume = volume;
+        self
+    }
+
+    /// Sets whether the alarm also plays on the other speakers grouped with the alarm's speaker
+    pub fn include_linked_zones(mut self, include_linked_zones: bool) -> Self {
+        self.include_linked_zones = include_linked_zones;
+        self
+    }
+}
@@ -1,15 +1,23 @@
 //! Resources for learning about speakers on the current network
 
 use std::{
-    net::{IpAddr, Ipv4Addr, UdpSocket},
-    time::{Duration, Instant},
+    collections::HashSet,
+    future::{self, Future},
+    net::{Ipv4Addr, SocketAddr},
+    time::Duration,
 };
 
 use reqwest::StatusCode;
+use tokio::{
+    net::UdpSocket,
+    sync::mpsc,
+    time::{timeout, Instant},
+};
+use tokio_stream::{wrappers::ReceiverStream, Stream};
 
 use crate::{
     errors::{SonosError, SpeakerError, UDPError},
-    speaker::BasicSpeakerInfo,
+    speaker::{BasicSpeakerInfo, Speaker},
     xml::{get_error_code, parse_description_xml},
 };
 
@@ -23,10 +31,30 @@ const DESCRIPTION_ENDPOINT: &str = "/xml/device_description.xml";
 
 /// Returns basic information about a speaker, if one is found at the given IP address
 /// * `ip_addr` - the IP of the speaker to query for information
+///
+/// Returns an error if the device at the given IP address is not made by Sonos
 pub async fn get_speaker_info(ip_addr: Ipv4Addr) -> Result<BasicSpeakerInfo, SpeakerError> {
-    let url = format!("http://{}:1400{}", ip_addr, DESCRIPTION_ENDPOINT);
+    get_speaker_info_with_client(&reqwest::Client::new(), ip_addr).await
+}
+
+/// Returns basic information about a speaker, if one is found at the given IP address, sending the request with the given client
+pub(crate) async fn get_speaker_info_with_client(
+    client: &reqwest::Client,
+    ip_addr: Ipv4Addr,
+) -> Result<BasicSpeakerInfo, SpeakerError> {
+    get_speaker_info_from_url(client, &description_url(ip_addr), ip_addr).await
+}
 
-    let response = reqwest::get(&url).await?;
+fn description_url(ip_addr: Ipv4Addr) -> String {
+    format!("http://{}:1400{}", ip_addr, DESCRIPTION_ENDPOINT)
+}
+
+async fn get_speaker_info_from_url(
+    client: &reqwest::Client,
+    url: &str,
+    ip_addr: Ipv4Addr,
+) -> Result<BasicSpeakerInfo, SpeakerError> {
+    let response = client.get(url).send().await?;
 
     let status = response.status();
     let xml_response = response.text().await?;
@@ -36,7 +64,9 @@ pub async fn get_speaker_info(ip_addr: Ipv4Addr) -> Result<BasicSpeakerInfo, Spe
 
         Ok(speaker_info)
     } else {
-        let error_code = get_error_code(xml_response)?;
+        let Ok(error_code) = get_error_code(xml_response) else {
+            return Err(SpeakerError::HttpError(status));
+        };
 
         Err(SpeakerError::from(SonosError::from_err_code(
             &error_code,
@@ -46,46 +76,495 @@ pub async fn get_speaker_info(ip_addr: Ipv4Addr) -> Result<BasicSpeakerInfo, Spe
 }
 
 /// Returns devices discovered on the current network within a given amount of time
-/// * `search_timeout` - how long the function will accept responses from speakers (the function will return in about this many seconds)
+/// * `search_timeout` - how long the function will accept responses from speakers (the function will return after about this long)
 /// * `read_timeout` - the maximum amount of time for which the function will try and read data from a given response
+///
+/// Devices that respond to the search but are not made by Sonos are left out of the results.
+/// If either duration is zero, no search is performed and an empty vector is returned
 pub async fn discover_devices(
     search_timeout: Duration,
     read_timeout: Duration,
 ) -> Result<Vec<BasicSpeakerInfo>, UDPError> {
-    let socket: UdpSocket = UdpSocket::bind("0.0.0.0:0")?;
+    discover_devices_on_interface(search_timeout, read_timeout, Ipv4Addr::UNSPECIFIED).await
+}
+
+/// Returns devices discovered within a given amount of time, searching from a specific local address
+/// * `search_timeout` - how long the function will accept responses from speakers (the function will return after about this long)
+/// * `read_timeout` - the maximum amount of time for which the function will try and read data from a given response
+/// * `bind_addr` - the local address of the network interface to search from
+///
+/// This is useful on machines with multiple network interfaces (VPNs, Docker bridges, etc...), where searching from `0.0.0.0` may use the wrong one.
+/// If the interface has no route for multicast or broadcast traffic, this will return an error when sending the search request,
+/// or return an empty vector if the request is sent but no speakers can respond
+///
+/// If either duration is zero, no search is performed and an empty vector is returned
+pub async fn discover_devices_on_interface(
+    search_timeout: Duration,
+    read_timeout: Duration,
+    bind_addr: Ipv4Addr,
+) -> Result<Vec<BasicSpeakerInfo>, UDPError> {
+    search_devices(
+        search_timeout,
+        read_timeout,
+        bind_addr,
+        &reqwest::Client::new(),
+        |_| true,
+    )
+    .await
+}
+
+/// Returns devices discovered on the current network within a given amount of time, fetching their descriptions with the given client
+pub(crate) async fn discover_devices_with_client(
+    search_timeout: Duration,
+    read_timeout: Duration,
+    client: &reqwest::Client,
+) -> Result<Vec<BasicSpeakerInfo>, UDPError> {
+    search_devices(
+        search_timeout,
+        read_timeout,
+        Ipv4Addr::UNSPECIFIED,
+        client,
+        |_| true,
+    )
+    .await
+}
+
+/// Returns devices discovered on the current network within a given amount of time, keeping only those that satisfy a filter
+/// * `search_timeout` - how long the function will accept responses from speakers (the function will return after about this long)
+/// * `read_timeout` - the maximum amount of time for which the function will try and read data from a given response
+/// * `filter` - returns whether a discovered speaker should be included in the results
+///
+/// If either duration is zero, no search is performed and an empty vector is returned
+pub async fn discover_devices_filtered<F>(
+    search_timeout: Duration,
+    read_timeout: Duration,
+    filter: F,
+) -> Result<Vec<BasicSpeakerInfo>, UDPError>
+where
+    F: Fn(&BasicSpeakerInfo) -> bool,
+{
+    search_devices(
+        search_timeout,
+        read_timeout,
+        Ipv4Addr::UNSPECIFIED,
+        &reqwest::Client::new(),
+        filter,
+    )
+    .await
+}
 
-    socket.set_broadcast(true)?;
+async fn search_devices<F>(
+    search_timeout: Duration,
+    read_timeout: Duration,
+    bind_addr: Ipv4Addr,
+    client: &reqwest::Client,
+    filter: F,
+) -> Result<Vec<BasicSpeakerInfo>, UDPError>
+where
+    F: Fn(&BasicSpeakerInfo) -> bool,
+{
+    let mut discovered_speakers = Vec::new();
 
-    socket.set_read_timeout(Some(read_timeout))?;
+    // if the socket cannot be read, the search ends early with the devices discovered so far
+    search(search_timeout, read_timeout, bind_addr, client, |info| {
+        if let Ok(info) = info {
+            if filter(&info) {
+                discovered_speakers.push(info);
+            }
+        }
 
-    socket.send_to(DISCOVERY_REQUEST_BODY.as_bytes(), "239.255.255.250:1900")?;
+        future::ready(true)
+    })
+    .await?;
 
-    socket.send_to(DISCOVERY_REQUEST_BODY.as_bytes(), "255.255.255.255:1900")?;
+    Ok(discovered_speakers)
+}
 
-    let start_time = Instant::now();
+/// Returns a stream of devices discovered on the current network, yielding each device as soon as it responds
+/// * `search_timeout` - how long to accept responses from speakers (the stream ends after about this long)
+/// * `read_timeout` - the maximum amount of time for which to try and read data from a given response
+///
+/// The search runs in a spawned task, so this must be called from within a tokio runtime.
+/// If a response cannot be read from the socket, the error is yielded and the stream ends.
+/// If either duration is zero, the stream ends immediately without yielding any devices
+pub fn discover_devices_stream(
+    search_timeout: Duration,
+    read_timeout: Duration,
+) -> impl Stream<Item = Result<BasicSpeakerInfo, SpeakerError>> {
+    let (sender, receiver) = mpsc::channel(16);
+
+    tokio::spawn(async move {
+        let client = reqwest::Client::new();
+
+        let result = search(
+            search_timeout,
+            read_timeout,
+            Ipv4Addr::UNSPECIFIED,
+            &client,
+            |info| {
+                let sender = sender.clone();
+
+                // once the stream is dropped, no one is listening for more devices
+                async move { sender.send(info.map_err(SpeakerError::from)).await.is_ok() }
+            },
+        )
+        .await;
+
+        if let Err(error) = result {
+            let _ = sender.send(Err(SpeakerError::from(error))).await;
+        }
+    });
+
+    ReceiverStream::new(receiver)
+}
+
+/// Searches for devices until the search times out, passing each newly discovered device to `on_device`
+///
+/// `on_device` returns whether to keep searching. If reading from the socket fails, the error is passed to `on_device` and the search ends
+async fn search<F, Fut>(
+    search_timeout: Duration,
+    read_timeout: Duration,
+    bind_addr: Ipv4Addr,
+    client: &reqwest::Client,
+    on_device: F,
+) -> Result<(), UDPError>
+where
+    F: FnMut(Result<BasicSpeakerInfo, UDPError>) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    if search_timeout.is_zero() || read_timeout.is_zero() {
+        return Ok(());
+    }
+
+    let socket = bind_discovery_socket(bind_addr).await?;
+
+    receive_responses(&socket, search_timeout, read_timeout, client, on_device).await;
+
+    Ok(())
+}
+
+/// Reads responses to a search request from the socket until the search times out or `on_device` returns false (see `search`)
+async fn receive_responses<F, Fut>(
+    socket: &UdpSocket,
+    search_timeout: Duration,
+    read_timeout: Duration,
+    client: &reqwest::Client,
+    mut on_device: F,
+) where
+    F: FnMut(Result<BasicSpeakerInfo, UDPError>) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    let deadline = Instant::now() + search_timeout;
 
     // this buffer is large enough to hold typical speaker response
     let mut buf = [0; 1024];
 
-    let mut discovered_speakers = Vec::new();
+    let mut discovered_speakers = HashSet::new();
 
     loop {
-        if start_time.elapsed() > search_timeout {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+
+        if remaining.is_zero() {
             break;
         }
 
-        if let Ok((_, addr)) = socket.recv_from(&mut buf) {
-            let ip_addr = addr.ip();
+        let received = timeout(remaining.min(read_timeout), socket.recv_from(&mut buf)).await;
 
-            if let IpAddr::V4(ip_addr) = ip_addr {
-                if let Ok(info) = get_speaker_info(ip_addr).await {
-                    if !discovered_speakers.contains(&info) {
-                        discovered_speakers.push(info);
+        // the read timing out only means that no response arrived in time, so the search continues
+        let keep_searching = match received {
+            Ok(Ok((len, SocketAddr::V4(addr)))) => {
+                let url = get_location(&buf[..len], *addr.ip());
+
+                match get_speaker_info_from_url(client, &url, *addr.ip()).await {
+                    Ok(info) if discovered_speakers.insert(info.clone()) => {
+                        on_device(Ok(info)).await
                     }
+                    _ => true,
                 }
             }
+            // errors reading from the socket (ex. the network going down) tend to repeat immediately, so they are not retried
+            Ok(Err(error)) => {
+                let _ = on_device(Err(UDPError::from(error))).await;
+                false
+            }
+            _ => true,
+        };
+
+        if !keep_searching {
+            break;
         }
     }
+}
 
-    Ok(discovered_speakers)
+async fn bind_discovery_socket(bind_addr: Ipv4Addr) -> Result<UdpSocket, UDPError> {
+    let socket = UdpSocket::bind((bind_addr, 0))
+        .await
+        .map_err(UDPError::BindError)?;
+
+    socket
+        .set_broadcast(true)
+        .map_err(UDPError::BroadcastError)?;
+
+    socket
+        .send_to(DISCOVERY_REQUEST_BODY.as_bytes(), "239.255.255.250:1900")
+        .await
+        .map_err(UDPError::SendError)?;
+
+    socket
+        .send_to(DISCOVERY_REQUEST_BODY.as_bytes(), "255.255.255.255:1900")
+        .await
+        .map_err(UDPError::SendError)?;
+
+    Ok(socket)
+}
+
+/// Returns the device description URL from the `LOCATION` header of an SSDP response,
+/// falling back to the default description URL for the responding IP if the header is missing
+fn get_location(response: &[u8], ip_addr: Ipv4Addr) -> String {
+    let response = String::from_utf8_lossy(response);
+
+    response
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("LOCATION"))
+        .map(|(_, value)| value.trim().to_owned())
+        .unwrap_or_else(|| description_url(ip_addr))
+}
+
+/// Returns a speaker whose room name matches the given name (case-insensitive), if one is discovered on the current network
+/// * `room_name` - the name of the room containing the speaker, ex. `Kitchen`
+/// * `search_timeout` - how long to search for speakers (see `discover_devices`)
+/// * `read_timeout` - the maximum amount of time for which to try and read data from a given response (see `discover_devices`)
+pub async fn find_by_room_name(
+    room_name: &str,
+    search_timeout: Duration,
+    read_timeout: Duration,
+) -> Result<Option<Speaker>, SpeakerError> {
+    let room_name = room_name.to_lowercase();
+
+    find_speaker(search_timeout, read_timeout, |info| {
+        info.room_name.to_lowercase() == room_name
+    })
+    .await
+}
+
+/// Returns a speaker whose friendly name exactly matches the given name, if one is discovered on the current network
+/// * `name` - the friendly name of the speaker, typically in the form `IP - Model`
+/// * `search_timeout` - how long to search for speakers (see `discover_devices`)
+/// * `read_timeout` - the maximum amount of time for which to try and read data from a given response (see `discover_devices`)
+pub async fn find_by_friendly_name(
+    name: &str,
+    search_timeout: Duration,
+    read_timeout: Duration,
+) -> Result<Option<Speaker>, SpeakerError> {
+    find_speaker(search_timeout, read_timeout, |info| {
+        info.friendly_name == name
+    })
+    .await
+}
+
+/// Returns a speaker whose friendly name contains the given string (case-insensitive), if one is discovered on the current network
+/// * `name` - the string to search for in the friendly name of the speaker, ex. `Play:1`
+/// * `search_timeout` - how long to search for speakers (see `discover_devices`)
+/// * `read_timeout` - the maximum amount of time for which to try and read data from a given response (see `discover_devices`)
+pub async fn find_by_friendly_name_containing(
+    name: &str,
+    search_timeout: Duration,
+    read_timeout: Duration,
+) -> Result<Option<Speaker>, SpeakerError> {
+    let name = name.to_lowercase();
+
+    find_speaker(search_timeout, read_timeout, |info| {
+        info.friendly_name.to_lowercase().contains(&name)
+    })
+    .await
+}
+
+/// Returns the speaker with the given UUID (case-insensitive), if it is discovered on the current network
+/// * `uuid` - the unique ID of the speaker, ex. `RINCON_000E58A0123401400`
+/// * `search_timeout` - how long to search for speakers (see `discover_devices`)
+/// * `read_timeout` - the maximum amount of time for which to try and read data from a given response (see `discover_devices`)
+pub async fn find_by_uuid(
+    uuid: &str,
+    search_timeout: Duration,
+    read_timeout: Duration,
+) -> Result<Option<Speaker>, SpeakerError> {
+    find_speaker(search_timeout, read_timeout, |info| {
+        info.uuid.eq_ignore_ascii_case(uuid)
+    })
+    .await
+}
+
+async fn find_speaker<F>(
+    search_timeout: Duration,
+    read_timeout: Duration,
+    predicate: F,
+) -> Result<Option<Speaker>, SpeakerError>
+where
+    F: Fn(&BasicSpeakerInfo) -> bool,
+{
+    let client = reqwest::Client::new();
+
+    let mut found = None;
+
+    search(
+        search_timeout,
+        read_timeout,
+        Ipv4Addr::UNSPECIFIED,
+        &client,
+        first_match(&predicate, &mut found),
+    )
+    .await?;
+
+    Ok(found.map(|info| Speaker::from_basic_info_with_client(info, client)))
+}
+
+/// Returns a callback for `search` that keeps the first device satisfying the predicate, then stops the search
+fn first_match<'a, F>(
+    predicate: &'a F,
+    found: &'a mut Option<BasicSpeakerInfo>,
+) -> impl FnMut(Result<BasicSpeakerInfo, UDPError>) -> future::Ready<bool> + 'a
+where
+    F: Fn(&BasicSpeakerInfo) -> bool,
+{
+    move |info| match info {
+        Ok(info) if predicate(&info) => {
+            *found = Some(info);
+            future::ready(false)
+        }
+        _ => future::ready(true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{convert::Infallible, time::Instant};
+
+    use hyper::{server::conn::Http, service::service_fn, Body, Request, Response};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    const DESCRIPTION_XML: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+<root xmlns="urn:schemas-upnp-org:device-1-0">
+  <device>
+    <friendlyName>127.0.0.1 - Sonos Play:5</friendlyName>
+    <manufacturer>Sonos, Inc.</manufacturer>
+    <modelNumber>S5</modelNumber>
+    <modelName>Sonos Play:5</modelName>
+    <softwareVersion>79.1-56030</softwareVersion>
+    <hardwareVersion>1.16.4.1-2.0</hardwareVersion>
+    <UDN>uuid:RINCON_000E58A0123401400</UDN>
+    <roomName>Kitchen</roomName>
+  </device>
+</root>"#;
+
+    // serves the device description of a speaker, returning the address it is served on
+    async fn serve_description() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let service = service_fn(|_: Request<Body>| async {
+                        Ok::<_, Infallible>(Response::new(Body::from(DESCRIPTION_XML)))
+                    });
+                    let _ = Http::new().serve_connection(stream, service).await;
+                });
+            }
+        });
+
+        addr
+    }
+
+    #[test]
+    fn builds_default_description_url() {
+        assert_eq!(
+            description_url(Ipv4Addr::new(192, 168, 1, 10)),
+            "http://192.168.1.10:1400/xml/device_description.xml"
+        );
+    }
+
+    #[test]
+    fn uses_location_header_for_description_url() {
+        let response = b"HTTP/1.1 200 OK\r\nCACHE-CONTROL: max-age = 1800\r\nLOCATION: http://192.168.1.10:1443/custom/description.xml\r\nST: urn:schemas-upnp-org:device:ZonePlayer:1\r\n\r\n";
+
+        assert_eq!(
+            get_location(response, Ipv4Addr::new(192, 168, 1, 10)),
+            "http://192.168.1.10:1443/custom/description.xml"
+        );
+    }
+
+    #[test]
+    fn falls_back_to_default_description_url() {
+        let response = b"HTTP/1.1 200 OK\r\nST: urn:schemas-upnp-org:device:ZonePlayer:1\r\n\r\n";
+
+        assert_eq!(
+            get_location(response, Ipv4Addr::new(192, 168, 1, 10)),
+            "http://192.168.1.10:1400/xml/device_description.xml"
+        );
+    }
+
+    #[test]
+    fn fetches_description_from_location() {
+        tokio_test::block_on(async {
+            let description_addr = serve_description().await;
+
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nLOCATION: http://{}/custom/description.xml\r\n\r\n",
+                description_addr
+            );
+            let url = get_location(response.as_bytes(), Ipv4Addr::LOCALHOST);
+
+            assert_eq!(
+                url,
+                format!("http://{}/custom/description.xml", description_addr)
+            );
+
+            let info =
+                get_speaker_info_from_url(&reqwest::Client::new(), &url, Ipv4Addr::LOCALHOST)
+                    .await
+                    .unwrap();
+
+            assert_eq!(info.ip_addr(), Ipv4Addr::LOCALHOST);
+            assert_eq!(info.room_name(), "Kitchen");
+        })
+    }
+
+    #[test]
+    fn stops_searching_at_first_match() {
+        tokio_test::block_on(async {
+            let description_addr = serve_description().await;
+
+            let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+
+            // a speaker responding to the search request
+            let responder = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nST: urn:schemas-upnp-org:device:ZonePlayer:1\r\nLOCATION: http://{}/xml/device_description.xml\r\n\r\n",
+                description_addr
+            );
+            responder
+                .send_to(response.as_bytes(), socket.local_addr().unwrap())
+                .await
+                .unwrap();
+
+            let predicate = |info: &BasicSpeakerInfo| info.room_name() == "Kitchen";
+            let mut found = None;
+
+            let started = Instant::now();
+            receive_responses(
+                &socket,
+                Duration::from_secs(10),
+                Duration::from_secs(10),
+                &reqwest::Client::new(),
+                first_match(&predicate, &mut found),
+            )
+            .await;
+
+            assert!(started.elapsed() < Duration::from_secs(5));
+            assert_eq!(found.unwrap().uuid(), "RINCON_000E58A0123401400");
+        })
+    }
 }
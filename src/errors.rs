@@ -1,5 +1,7 @@
 //! Crate error types
 
+use crate::services::Service;
+
 /// An XML-related error
 #[derive(Debug)]
 pub enum XMLError {
@@ -48,7 +50,57 @@ impl std::error::Error for XMLError {
 }
 
 /// Represents an error involving a UDP socket
-pub type UDPError = std::io::Error;
+#[derive(Debug)]
+pub enum UDPError {
+    /// Error binding the socket to a local address
+    BindError(std::io::Error),
+    /// Error enabling broadcast on the socket
+    BroadcastError(std::io::Error),
+    /// Error sending data from the socket
+    SendError(std::io::Error),
+    /// Timed out while reading data from the socket
+    ///
+    /// Discovery does not return this, since a read timing out there only means that no more speakers responded
+    ReadTimeoutError(std::io::Error),
+    /// Some other error while using the socket, such as failing to read a response during discovery
+    IoError(std::io::Error),
+}
+
+impl UDPError {
+    fn io_error(&self) -> &std::io::Error {
+        match self {
+            Self::BindError(source)
+            | Self::BroadcastError(source)
+            | Self::SendError(source)
+            | Self::ReadTimeoutError(source)
+            | Self::IoError(source) => source,
+        }
+    }
+}
+
+impl From<std::io::Error> for UDPError {
+    fn from(error: std::io::Error) -> Self {
+        Self::IoError(error)
+    }
+}
+
+impl std::fmt::Display for UDPError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::BindError(source) => write!(f, "error binding socket: {}", source),
+            Self::BroadcastError(source) => write!(f, "error enabling broadcast: {}", source),
+            Self::SendError(source) => write!(f, "error sending data: {}", source),
+            Self::ReadTimeoutError(source) => write!(f, "timed out reading data: {}", source),
+            Self::IoError(source) => write!(f, "socket error: {}", source),
+        }
+    }
+}
+
+impl std::error::Error for UDPError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(self.io_error())
+    }
+}
 
 /// Errors that may be returned from speaker methods
 #[derive(Debug)]
@@ -63,6 +115,36 @@ pub enum SpeakerError {
     InvalidInput(String),
     /// A speaker-specific error
     SonosError(SonosError),
+    /// An error that occurred while using a UDP socket, such as during discovery
+    UDPError(UDPError),
+    /// An error that occurred while making a speaker join a group, with the string containing the UUID of that speaker
+    GroupJoinError(String, Box<SpeakerError>),
+    /// The speaker responded with an unsuccessful HTTP status, without a UPnP error code describing the error
+    HttpError(reqwest::StatusCode),
+}
+
+impl SpeakerError {
+    /// Returns whether the error is likely transient, such as a timeout, a failed connection, or a 503 (service unavailable) response,
+    /// so that retrying the same action may succeed
+    ///
+    /// Errors caused by invalid input, malformed XML, or the speaker rejecting the action are not retryable
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RequestError(source) | Self::ResponseError(source) => {
+                source.is_timeout() || source.is_connect()
+            }
+            Self::UDPError(UDPError::ReadTimeoutError(_)) => true,
+            Self::UDPError(source) => matches!(
+                source.io_error().kind(),
+                std::io::ErrorKind::TimedOut
+                    | std::io::ErrorKind::Interrupted
+                    | std::io::ErrorKind::ConnectionReset
+            ),
+            Self::GroupJoinError(_, source) => source.is_retryable(),
+            Self::HttpError(status) => *status == reqwest::StatusCode::SERVICE_UNAVAILABLE,
+            Self::XMLError(_) | Self::InvalidInput(_) | Self::SonosError(_) => false,
+        }
+    }
 }
 
 impl From<XMLError> for SpeakerError {
@@ -77,6 +159,12 @@ impl From<SonosError> for SpeakerError {
     }
 }
 
+impl From<UDPError> for SpeakerError {
+    fn from(error: UDPError) -> Self {
+        Self::UDPError(error)
+    }
+}
+
 impl From<reqwest::Error> for SpeakerError {
     fn from(error: reqwest::Error) -> Self {
         if error.is_builder()
@@ -100,6 +188,11 @@ impl std::fmt::Display for SpeakerError {
             Self::ResponseError(source) => write!(f, "response error: {}", source),
             Self::SonosError(source) => write!(f, "Sonos speaker error: {}", source),
             Self::XMLError(source) => write!(f, "XML error: {}", source),
+            Self::UDPError(source) => write!(f, "UDP error: {}", source),
+            Self::GroupJoinError(uuid, source) => {
+                write!(f, "error adding speaker {} to group: {}", uuid, source)
+            }
+            Self::HttpError(status) => write!(f, "HTTP error: {}", status),
         }
     }
 }
@@ -112,6 +205,9 @@ impl std::error::Error for SpeakerError {
             Self::ResponseError(source) => Some(source),
             Self::SonosError(source) => Some(source),
             Self::XMLError(source) => Some(source),
+            Self::UDPError(source) => Some(source),
+            Self::GroupJoinError(_, source) => Some(source.as_ref()),
+            Self::HttpError(_) => None,
         }
     }
 }
@@ -121,18 +217,127 @@ impl std::error::Error for SpeakerError {
 pub enum SonosError {
     /// Not able to change transition, ex. pausing when playback is already paused
     TransitionUnavailable,
+    /// There is no content to play, ex. playing with nothing selected
+    NoContents,
+    /// The media could not be read
+    ReadError,
+    /// The format of the media is not supported for playback
+    PlaybackFormatUnsupported,
+    /// The transport is locked, so it cannot be controlled
+    TransportLocked,
+    /// The media could not be written
+    WriteError,
+    /// The media is protected or not writeable
+    MediaProtected,
+    /// The format of the media is not supported for recording
+    RecordingFormatUnsupported,
+    /// The media is full
+    MediaFull,
+    /// The unit used for seeking is not supported
+    SeekModeUnsupported,
     /// Invalid target for operations such as seek (ex. an invalid duration) or next (using next at the end of the queue, or while not in a queue)
     InvalidSeekTarget,
-    /// Some other Sonos error, with the string containing additional data
-    Unknown(String),
+    /// The play mode is not supported
+    PlayModeUnsupported,
+    /// The speaker is in a group but does not lead it, so it cannot control playback (send the command to the group coordinator instead)
+    NotCoordinator,
+    /// The instance ID sent to the rendering control service is invalid
+    InvalidInstanceId,
+    /// The value of an argument is invalid
+    ArgumentValueInvalid,
+    /// The value of an argument is out of the allowed range
+    ArgumentValueOutOfRange,
+    /// The action is not implemented by the speaker
+    ActionNotImplemented,
+    /// Some other Sonos error
+    Unknown {
+        /// The error code returned by the speaker (0 if the code was not a number)
+        code: u16,
+        /// Additional details about the error
+        detail: String,
+    },
 }
 
 impl SonosError {
+    /// Maps an error code to the error it means for the service that returned it
+    pub(crate) fn from_service_err_code(
+        service: &Service,
+        err_code: &str,
+        additional_details: &str,
+    ) -> Self {
+        match service {
+            Service::AVTransport => Self::from_err_code(err_code, additional_details),
+            Service::RenderingControl => {
+                Self::from_rendering_control_err_code(err_code, additional_details)
+            }
+            // other services reuse codes from the 700s with their own meanings (ex. 701 is "no such object" when browsing)
+            _ => Self::from_upnp_err_code(err_code, additional_details),
+        }
+    }
+
     pub(crate) fn from_err_code(err_code: &str, additional_details: &str) -> Self {
         match err_code {
             "701" => Self::TransitionUnavailable,
+            "702" => Self::NoContents,
+            "703" => Self::ReadError,
+            "704" => Self::PlaybackFormatUnsupported,
+            "705" => Self::TransportLocked,
+            "706" => Self::WriteError,
+            "707" => Self::MediaProtected,
+            "708" => Self::RecordingFormatUnsupported,
+            "709" => Self::MediaFull,
+            "710" => Self::SeekModeUnsupported,
             "711" => Self::InvalidSeekTarget,
-            _ => Self::Unknown(String::from(additional_details)),
+            "712" => Self::PlayModeUnsupported,
+            "800" => Self::NotCoordinator,
+            _ => Self::from_upnp_err_code(err_code, additional_details),
+        }
+    }
+
+    pub(crate) fn from_rendering_control_err_code(
+        err_code: &str,
+        additional_details: &str,
+    ) -> Self {
+        match err_code {
+            "702" => Self::InvalidInstanceId,
+            _ => Self::from_upnp_err_code(err_code, additional_details),
+        }
+    }
+
+    /// Maps error codes shared by all UPnP services
+    fn from_upnp_err_code(err_code: &str, additional_details: &str) -> Self {
+        match err_code {
+            "600" => Self::ArgumentValueInvalid,
+            "601" => Self::ArgumentValueOutOfRange,
+            "602" => Self::ActionNotImplemented,
+            _ => Self::Unknown {
+                code: err_code.trim().parse().unwrap_or(0),
+                detail: String::from(additional_details),
+            },
+        }
+    }
+
+    /// Returns the UPnP error code corresponding to this error
+    pub fn error_code(&self) -> u16 {
+        match self {
+            Self::TransitionUnavailable => 701,
+            Self::NoContents => 702,
+            Self::ReadError => 703,
+            Self::PlaybackFormatUnsupported => 704,
+            Self::TransportLocked => 705,
+            Self::WriteError => 706,
+            Self::MediaProtected => 707,
+            Self::RecordingFormatUnsupported => 708,
+            Self::MediaFull => 709,
+            Self::SeekModeUnsupported => 710,
+            Self::InvalidSeekTarget => 711,
+            Self::PlayModeUnsupported => 712,
+            Self::NotCoordinator => 800,
+            Self::InvalidInstanceId => 702,
+            Self::ArgumentValueInvalid => 600,
+            Self::ArgumentValueOutOfRange => 601,
+            Self::ActionNotImplemented => 602,
+            Self::Unknown { code, .. } => *code,
         }
     }
 }
@@ -141,10 +346,76 @@ impl std::fmt::Display for SonosError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::TransitionUnavailable => write!(f, "transition unavailable"),
+            Self::NoContents => write!(f, "no contents"),
+            Self::ReadError => write!(f, "read error"),
+            Self::PlaybackFormatUnsupported => write!(f, "format not supported for playback"),
+            Self::TransportLocked => write!(f, "transport is locked"),
+            Self::WriteError => write!(f, "write error"),
+            Self::MediaProtected => write!(f, "media is protected or not writeable"),
+            Self::RecordingFormatUnsupported => write!(f, "format not supported for recording"),
+            Self::MediaFull => write!(f, "media is full"),
+            Self::SeekModeUnsupported => write!(f, "seek mode not supported"),
             Self::InvalidSeekTarget => write!(f, "invalid seek target"),
-            Self::Unknown(s) => write!(f, "other Sonos error: {}", s),
+            Self::PlayModeUnsupported => write!(f, "play mode not supported"),
+            Self::NotCoordinator => write!(
+                f,
+                "speaker is not the coordinator of its group (use get_group_coordinator to find the speaker that controls playback)"
+            ),
+            Self::InvalidInstanceId => write!(f, "invalid instance ID"),
+            Self::ArgumentValueInvalid => write!(f, "argument value invalid"),
+            Self::ArgumentValueOutOfRange => write!(f, "argument value out of range"),
+            Self::ActionNotImplemented => write!(f, "action not implemented"),
+            Self::Unknown { code, detail } => {
+                write!(f, "other Sonos error (code {}): {}", code, detail)
+            }
         }
     }
 }
 
 impl std::error::Error for SonosError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_av_transport_codes() {
+        assert!(matches!(
+            SonosError::from_service_err_code(&Service::AVTransport, "701", ""),
+            SonosError::TransitionUnavailable
+        ));
+    }
+
+    #[test]
+    fn maps_content_directory_codes_without_av_transport_meanings() {
+        assert!(matches!(
+            SonosError::from_service_err_code(&Service::ContentDirectory, "701", "no such object"),
+            SonosError::Unknown { code: 701, .. }
+        ));
+        assert!(matches!(
+            SonosError::from_service_err_code(&Service::ContentDirectory, "800", ""),
+            SonosError::Unknown { code: 800, .. }
+        ));
+    }
+
+    #[test]
+    fn maps_shared_upnp_codes_for_every_service() {
+        assert!(matches!(
+            SonosError::from_service_err_code(&Service::AlarmClock, "602", ""),
+            SonosError::ActionNotImplemented
+        ));
+    }
+
+    #[test]
+    fn service_unavailable_is_retryable() {
+        assert!(SpeakerError::HttpError(reqwest::StatusCode::SERVICE_UNAVAILABLE).is_retryable());
+    }
+
+    #[test]
+    fn other_http_errors_are_not_retryable() {
+        assert!(!SpeakerError::HttpError(reqwest::StatusCode::NOT_FOUND).is_retryable());
+        assert!(
+            !SpeakerError::HttpError(reqwest::StatusCode::INTERNAL_SERVER_ERROR).is_retryable()
+        );
+    }
+}
@@ -0,0 +1,328 @@
+//! Resources for receiving events from speakers when their state changes
+//!
+//! Speakers use UPnP eventing: after subscribing with a callback URL, a speaker sends HTTP `NOTIFY` requests to that URL whenever its state changes,
+//! until the subscription expires or is cancelled. `EventServer` receives these requests and turns them into `SpeakerEvent`s
+//!
+//! ```rust,no_run
+//! # tokio_test::block_on(async {
+//! # use rusty_sonos::{events::EventServer, speaker::Speaker};
+//! # use std::net::Ipv4Addr;
+//! let server = EventServer::bind("0.0.0.0:3400".parse().unwrap()).await.unwrap();
+//! let mut events = server.subscribe();
+//!
+//! let speaker = Speaker::new(Ipv4Addr::new(192, 168, 1, 10)).await.unwrap();
+//! let subscription = speaker
+//!     .subscribe_to_av_transport_events("http://192.168.1.2:3400/events", 1800)
+//!     .await
+//!     .unwrap();
+//!
+//! while let Ok(event) = events.recv().await {
+//!     println!("{:?}", event);
+//! }
+//! # })
+//! ```
+
+use std::{
+    convert::Infallible,
+    net::SocketAddr,
+    time::{Duration, Instant},
+};
+
+use hyper::{server::conn::Http, service::service_fn, Body, Request, Response, StatusCode};
+use tokio::{net::TcpListener, sync::broadcast, task::JoinHandle};
+
+use crate::{
+    errors::{SpeakerError, XMLError},
+    responses::PlaybackState,
+    speaker::Speaker,
+    xml::{self, parse_event_xml},
+};
+
+// the number of events kept for receivers that have fallen behind
+const EVENT_CHANNEL_CAPACITY: usize = 64;
+
+// how long to wait before accepting again after a failed accept (ex. too many open files), so the server does not spin
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+// the shortest time to wait between renewals, so a speaker reporting a zero or tiny timeout is not renewed in a tight loop
+const MIN_RENEWAL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A change in the state of a speaker, received from a subscription
+#[derive(Debug, Clone)]
+pub enum SpeakerEvent {
+    /// The current track changed
+    TrackChanged {
+        /// The ID of the subscription the event was received from
+        sid: String,
+        /// The source URI of the new track
+        uri: String,
+        /// The DIDL-Lite metadata of the new track
+        metadata: String,
+    },
+    /// The volume changed
+    VolumeChanged {
+        /// The ID of the subscription the event was received from
+        sid: String,
+        /// The new volume
+        volume: u8,
+    },
+    /// The playback state changed
+    PlaybackStateChanged {
+        /// The ID of the subscription the event was received from
+        sid: String,
+        /// The new playback state
+        state: PlaybackState,
+    },
+}
+
+/// A single value that changed, as described by the `LastChange` property of an event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransportEvent {
+    /// The name of the value that changed, ex. `TransportState` or `CurrentTrackURI`
+    pub field: String,
+    /// The new value
+    pub value: String,
+}
+
+/// Parses the `LastChange` property of an event into the values that changed
+/// * `last_change` - the XML describing the changes, either as sent in the event body (once unescaped) or URI-encoded
+///
+/// ```rust
+/// # use rusty_sonos::events::parse_last_change_xml;
+/// let last_change = r#"<Event><InstanceID val="0"><TransportState val="PLAYING"/></InstanceID></Event>"#;
+///
+/// let events = parse_last_change_xml(last_change).unwrap();
+///
+/// assert_eq!(events[0].field, "TransportState");
+/// assert_eq!(events[0].value, "PLAYING");
+/// ```
+pub fn parse_last_change_xml(last_change: &str) -> Result<Vec<TransportEvent>, XMLError> {
+    xml::parse_last_change_xml(last_change)
+}
+
+/// An HTTP server that receives events from speakers and forwards them to subscribers
+///
+/// The server stops when it is dropped
+pub struct EventServer {
+    local_addr: SocketAddr,
+    sender: broadcast::Sender<SpeakerEvent>,
+    handle: JoinHandle<()>,
+}
+
+impl EventServer {
+    /// Starts a server that listens for events on the given address
+    ///
+    /// * `addr` - the local address to listen on (use port 0 to choose any free port, see `local_addr`)
+    ///
+    /// The server runs in a spawned task, so this must be called from within a tokio runtime
+    pub async fn bind(addr: SocketAddr) -> Result<Self, std::io::Error> {
+        let listener = TcpListener::bind(addr).await?;
+        let local_addr = listener.local_addr()?;
+
+        let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+
+        let server_sender = sender.clone();
+        let handle = tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+                    continue;
+                };
+
+                let sender = server_sender.clone();
+                tokio::spawn(async move {
+                    let service = service_fn(move |request| handle_notify(request, sender.clone()));
+
+                    // errors on a single connection, such as the speaker disconnecting early, do not affect the server
+                    let _ = Http::new()
+                        .http1_only(true)
+                        .serve_connection(stream, service)
+                        .await;
+                });
+            }
+        });
+
+        Ok(EventServer {
+            local_addr,
+            sender,
+            handle,
+        })
+    }
+
+    /// The local address the server is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns a receiver for events received after this call
+    pub fn subscribe(&self) -> broadcast::Receiver<SpeakerEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl Drop for EventServer {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn handle_notify(
+    request: Request<Body>,
+    sender: broadcast::Sender<SpeakerEvent>,
+) -> Result<Response<Body>, Infallible> {
+    if request.method().as_str() != "NOTIFY" {
+        return Ok(empty_response(StatusCode::METHOD_NOT_ALLOWED));
+    }
+
+    let headers = request.headers();
+
+    let nt = headers.get("NT").and_then(|nt| nt.to_str().ok());
+    let sid = headers.get("SID").and_then(|sid| sid.to_str().ok());
+
+    let (Some("upnp:event"), Some(sid)) = (nt, sid) else {
+        return Ok(empty_response(StatusCode::PRECONDITION_FAILED));
+    };
+
+    let sid = sid.to_owned();
+
+    let Ok(body) = hyper::body::to_bytes(request.into_body()).await else {
+        return Ok(empty_response(StatusCode::BAD_REQUEST));
+    };
+
+    let body = String::from_utf8_lossy(&body).into_owned();
+
+    let Ok(events) = parse_event_xml(body, &sid) else {
+        return Ok(empty_response(StatusCode::BAD_REQUEST));
+    };
+
+    for event in events {
+        // sending only fails when there are no receivers, in which case the event is dropped
+        let _ = sender.send(event);
+    }
+
+    Ok(empty_response(StatusCode::OK))
+}
+
+fn empty_response(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+/// A subscription to events from a speaker service
+#[derive(Debug, Clone)]
+pub struct EventSubscription {
+    pub(crate) sid: String,
+    pub(crate) timeout: Duration,
+    pub(crate) expires_at: Instant,
+    pub(crate) event_endpoint: &'static str,
+}
+
+impl EventSubscription {
+    /// The subscription ID assigned by the speaker, sent with each event in the `SID` header
+    pub fn sid(&self) -> &str {
+        &self.sid
+    }
+
+    /// How long the subscription lasts after it is created or renewed
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// When the subscription expires, unless it is renewed
+    pub fn expires_at(&self) -> Instant {
+        self.expires_at
+    }
+
+    /// Renews the subscription, so that it lasts for another `timeout` from now
+    ///
+    /// * `speaker` - the speaker the subscription was created with
+    pub async fn renew(&mut self, speaker: &Speaker) -> Result<(), SpeakerError> {
+        let requested_timeout = format!("Second-{}", self.timeout.as_secs());
+
+        let response = speaker
+            .send_event_request(
+                "SUBSCRIBE",
+                self.event_endpoint,
+                &[
+                    ("SID", self.sid.as_str()),
+                    ("TIMEOUT", requested_timeout.as_str()),
+                ],
+            )
+            .await?;
+
+        if let Some(timeout) = response
+            .headers()
+            .get("TIMEOUT")
+            .and_then(|timeout| timeout.to_str().ok())
+            .and_then(parse_timeout_header)
+        {
+            self.timeout = timeout;
+        }
+
+        self.expires_at = Instant::now() + self.timeout;
+
+        Ok(())
+    }
+
+    /// Cancels the subscription, so that the speaker stops sending events
+    ///
+    /// * `speaker` - the speaker the subscription was created with
+    pub async fn unsubscribe(&self, speaker: &Speaker) -> Result<(), SpeakerError> {
+        let _ = speaker
+            .send_event_request("UNSUBSCRIBE", self.event_endpoint, &[("SID", &self.sid)])
+            .await?;
+
+        Ok(())
+    }
+
+    /// Spawns a task that keeps the subscription alive, renewing it after 80% of its timeout has passed each time (but at most once a second)
+    ///
+    /// * `speaker` - the speaker the subscription was created with
+    ///
+    /// The task stops if a renewal fails (ex. because the speaker is unreachable), and can be stopped early by aborting the returned handle.
+    /// This must be called from within a tokio runtime
+    pub fn auto_renew(mut self, speaker: Speaker) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(renewal_interval(self.timeout)).await;
+
+                if self.renew(&speaker).await.is_err() {
+                    break;
+                }
+            }
+        })
+    }
+}
+
+/// Returns how long to wait before renewing a subscription with the given timeout
+fn renewal_interval(timeout: Duration) -> Duration {
+    timeout.mul_f64(0.8).max(MIN_RENEWAL_INTERVAL)
+}
+
+/// Parses a `TIMEOUT` header from a subscription response, ex. `Second-1800`
+pub(crate) fn parse_timeout_header(timeout: &str) -> Option<Duration> {
+    timeout
+        .trim()
+        .strip_prefix("Second-")
+        .and_then(|seconds| seconds.parse::<u64>().ok())
+        .map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renews_before_timeout() {
+        assert_eq!(
+            renewal_interval(Duration::from_secs(1800)),
+            Duration::from_secs(1440)
+        );
+    }
+
+    #[test]
+    fn renews_at_most_once_a_second() {
+        assert_eq!(renewal_interval(Duration::ZERO), MIN_RENEWAL_INTERVAL);
+    }
+}
@@ -0,0 +1,126 @@
+//! Resources for controlling groups of speakers together
+
+use crate::{errors::SpeakerError, speaker::Speaker};
+
+/// Groups all of the given speakers together, led by the first speaker, and returns the new group
+/// * `speakers` - the speakers to group, where the first speaker becomes the coordinator
+///
+/// Speakers join the group one at a time. If one fails to join, an error containing its UUID is returned,
+/// and speakers that already joined are left in the group
+pub async fn party_mode(speakers: &[Speaker]) -> Result<SpeakerGroup, SpeakerError> {
+    let (coordinator, others) = speakers
+        .split_first()
+        .ok_or_else(|| SpeakerError::InvalidInput(String::from("no speakers to group")))?;
+
+    let mut members = Vec::new();
+
+    for speaker in others {
+        speaker
+            .join_group(coordinator)
+            .await
+            .map_err(|error| SpeakerError::GroupJoinError(speaker.get_uuid(), Box::new(error)))?;
+
+        members.push(speaker.clone());
+    }
+
+    Ok(SpeakerGroup {
+        coordinator: coordinator.clone(),
+        members,
+    })
+}
+
+/// A group of speakers playing the same audio, led by a coordinator speaker
+pub struct SpeakerGroup {
+    pub(crate) coordinator: Speaker,
+    pub(crate) members: Vec<Speaker>,
+}
+
+impl SpeakerGroup {
+    /// Returns the speaker leading the group
+    pub fn coordinator(&self) -> &Speaker {
+        &self.coordinator
+    }
+
+    /// Returns the speakers in the group other than the coordinator
+    pub fn members(&self) -> &[Speaker] {
+        &self.members
+    }
+
+    /// Starts playback of the current track on the group
+    pub async fn play(&self) -> Result<(), SpeakerError> {
+        self.coordinator.play().await
+    }
+
+    /// Pauses playback on the group
+    pub async fn pause(&self) -> Result<(), SpeakerError> {
+        self.coordinator.pause().await
+    }
+
+    /// Stops playback on the group
+    pub async fn stop(&self) -> Result<(), SpeakerError> {
+        self.coordinator.stop().await
+    }
+
+    /// Returns the volume of the group's coordinator
+    pub async fn get_volume(&self) -> Result<u8, SpeakerError> {
+        self.coordinator.get_volume().await
+    }
+
+    /// Changes the volume of every speaker in the group to the specified value
+    ///
+    /// * `new_volume` - the volume to set the speakers to, between 0 and 100 inclusive
+    pub async fn set_volume(&self, new_volume: u8) -> Result<(), SpeakerError> {
+        for speaker in self.speakers() {
+            speaker.set_volume(new_volume).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Mutes or unmutes every speaker in the group
+    ///
+    /// * `muted` - whether the speakers should be muted
+    pub async fn set_mute(&self, muted: bool) -> Result<(), SpeakerError> {
+        for speaker in self.speakers() {
+            if muted {
+                speaker.mute().await?;
+            } else {
+                speaker.unmute().await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Makes a speaker join the group
+    ///
+    /// * `speaker` - the speaker to add to the group
+    pub async fn add_member(&mut self, speaker: Speaker) -> Result<(), SpeakerError> {
+        speaker.join_group(&self.coordinator).await?;
+
+        self.members.push(speaker);
+
+        Ok(())
+    }
+
+    /// Makes a speaker leave the group
+    ///
+    /// * `uuid` - the UUID of the speaker to remove, which must not be the coordinator
+    pub async fn remove_member(&mut self, uuid: &str) -> Result<(), SpeakerError> {
+        let index = self
+            .members
+            .iter()
+            .position(|speaker| speaker.uuid == uuid)
+            .ok_or_else(|| SpeakerError::InvalidInput(format!("not a group member: {}", uuid)))?;
+
+        self.members[index].leave_group().await?;
+
+        self.members.remove(index);
+
+        Ok(())
+    }
+
+    fn speakers(&self) -> impl Iterator<Item = &Speaker> {
+        std::iter::once(&self.coordinator).chain(&self.members)
+    }
+}
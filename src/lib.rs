@@ -29,6 +29,22 @@
 //! # })
 //! ```
 //!
+//! Handling speakers as soon as they are discovered:
+//! ```rust,no_run
+//! # tokio_test::block_on(async {
+//! # use rusty_sonos::discovery::discover_devices_stream;
+//! # use std::time::Duration;
+//! use tokio_stream::StreamExt;
+//!
+//! let mut devices = discover_devices_stream(Duration::from_secs(2), Duration::from_secs(5));
+//!
+//! while let Some(device) = devices.next().await {
+//!    let device = device.unwrap();
+//!    println!("{}, {}", device.friendly_name(), device.room_name())
+//! }
+//! # })
+//! ```
+//!
 //! Get information about a speaker at a certain IP address
 //! ```rust,no_run
 //! # tokio_test::block_on(async {
@@ -41,9 +57,15 @@
 //! # })
 //! ```
 
+pub mod alarm;
 pub mod discovery;
 pub mod errors;
+pub mod events;
+pub mod group;
+pub mod manager;
+pub mod metadata;
 pub mod responses;
 mod services;
 pub mod speaker;
+pub mod time;
 mod xml;
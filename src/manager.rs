@@ -0,0 +1,70 @@
+//! Resources for managing multiple speakers at once
+
+use std::time::Duration;
+
+use crate::{discovery::discover_devices_with_client, errors::UDPError, speaker::Speaker};
+
+/// A collection of speakers, all sharing a single HTTP client
+pub struct SpeakerManager {
+    client: reqwest::Client,
+    speakers: Vec<Speaker>,
+}
+
+impl SpeakerManager {
+    /// Creates a manager containing all speakers discovered on the current network within a given amount of time
+    /// * `search_timeout` - how long to search for speakers (see `discover_devices`)
+    /// * `read_timeout` - the maximum amount of time for which to try and read data from a given response (see `discover_devices`)
+    pub async fn discover(
+        search_timeout: Duration,
+        read_timeout: Duration,
+    ) -> Result<Self, UDPError> {
+        let client = reqwest::Client::new();
+
+        let speakers = discover_devices_with_client(search_timeout, read_timeout, &client)
+            .await?
+            .into_iter()
+            .map(|info| Speaker::from_basic_info_with_client(info, client.clone()))
+            .collect();
+
+        Ok(SpeakerManager { client, speakers })
+    }
+
+    /// Returns the HTTP client shared by all managed speakers
+    pub fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Returns all managed speakers
+    pub fn all(&self) -> &[Speaker] {
+        &self.speakers
+    }
+
+    /// Returns the first speaker in the given room (case-insensitive), if there is one
+    /// * `room_name` - the name of the room containing the speaker, ex. `Kitchen`
+    pub fn get_by_room_name(&self, room_name: &str) -> Option<&Speaker> {
+        let room_name = room_name.to_lowercase();
+
+        self.speakers
+            .iter()
+            .find(|speaker| speaker.room_name.to_lowercase() == room_name)
+    }
+
+    /// Returns the speaker with the given UUID (case-insensitive), if there is one
+    /// * `uuid` - the unique ID of the speaker
+    pub fn get_by_uuid(&self, uuid: &str) -> Option<&Speaker> {
+        self.speakers
+            .iter()
+            .find(|speaker| speaker.uuid.eq_ignore_ascii_case(uuid))
+    }
+
+    /// Returns all speakers in the given room (case-insensitive), such as both speakers in a stereo pair
+    /// * `room_name` - the name of the room containing the speakers, ex. `Kitchen`
+    pub fn find_all_in_room(&self, room_name: &str) -> Vec<&Speaker> {
+        let room_name = room_name.to_lowercase();
+
+        self.speakers
+            .iter()
+            .filter(|speaker| speaker.room_name.to_lowercase() == room_name)
+            .collect()
+    }
+}
@@ -0,0 +1,120 @@
+//! Metadata describing audio sources, shown by Sonos apps and speaker displays
+//!
+//! ```rust
+//! # use rusty_sonos::metadata::DidlMetadata;
+//! let metadata = DidlMetadata::for_track("http://192.168.1.2/song.mp3")
+//!     .title("Song")
+//!     .artist("Artist")
+//!     .duration("0:03:00");
+//!
+//! let xml = metadata.to_xml_string().unwrap();
+//! ```
+
+use crate::{errors::XMLError, xml::generate_didl_xml};
+
+/// The `upnp:class` of a music track
+pub const MUSIC_TRACK_CLASS: &str = "object.item.audioItem.musicTrack";
+
+/// The `upnp:class` of a radio station or other live stream
+pub const AUDIO_BROADCAST_CLASS: &str = "object.item.audioItem.audioBroadcast";
+
+/// Information about an audio source, sent to the speaker as DIDL-Lite XML
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DidlMetadata {
+    /// The ID of the item (if `None`, `-1` is used)
+    pub id: Option<String>,
+    /// The URI of the audio source (if `None`, `duration` is not included either)
+    pub uri: Option<String>,
+    /// The title of the track
+    pub title: Option<String>,
+    /// The artist/creator of the track
+    pub artist: Option<String>,
+    /// The album containing the track
+    pub album: Option<String>,
+    /// The URI of the album art for the track
+    pub album_art_uri: Option<String>,
+    /// The length of the track, as hh:mm:ss
+    pub duration: Option<String>,
+    /// The UPnP class of the source (if `None`, the source is treated as a music track)
+    pub class: Option<String>,
+}
+
+impl DidlMetadata {
+    /// Creates metadata for a music track
+    ///
+    /// * `uri` - the URI of the track
+    pub fn for_track(uri: &str) -> Self {
+        DidlMetadata {
+            uri: Some(uri.to_owned()),
+            class: Some(MUSIC_TRACK_CLASS.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    /// Creates metadata for a radio station or other live stream
+    ///
+    /// * `title` - the name of the stream
+    /// * `stream_url` - the URL of the stream
+    pub fn for_stream(title: &str, stream_url: &str) -> Self {
+        DidlMetadata {
+            uri: Some(stream_url.to_owned()),
+            title: Some(title.to_owned()),
+            class: Some(AUDIO_BROADCAST_CLASS.to_owned()),
+            ..Default::default()
+        }
+    }
+
+    /// Sets the ID of the item
+    pub fn id(mut self, id: &str) -> Self {
+        self.id = Some(id.to_owned());
+        self
+    }
+
+    /// Sets the URI of the audio source
+    pub fn uri(mut self, uri: &str) -> Self {
+        self.uri = Some(uri.to_owned());
+        self
+    }
+
+    /// Sets the title of the track
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = Some(title.to_owned());
+        self
+    }
+
+    /// Sets the artist/creator of the track
+    pub fn artist(mut self, artist: &str) -> Self {
+        self.artist = Some(artist.to_owned());
+        self
+    }
+
+    /// Sets the album containing the track
+    pub fn album(mut self, album: &str) -> Self {
+        self.album = Some(album.to_owned());
+        self
+    }
+
+    /// Sets the URI of the album art for the track
+    pub fn album_art_uri(mut self, album_art_uri: &str) -> Self {
+        self.album_art_uri = Some(album_art_uri.to_owned());
+        self
+    }
+
+    /// Sets the length of the track, as hh:mm:ss
+    pub fn duration(mut self, duration: &str) -> Self {
+        self.duration = Some(duration.to_owned());
+        self
+    }
+
+    /// Sets the UPnP class of the source, such as `MUSIC_TRACK_CLASS`
+    pub fn class(mut self, class: &str) -> Self {
+        self.class = Some(class.to_owned());
+        self
+    }
+
+    /// Returns the metadata as DIDL-Lite XML, as expected by speakers
+    pub fn to_xml_string(&self) -> Result<String, XMLError> {
+        generate_didl_xml(self)
+    }
+}
@@ -1,9 +1,12 @@
 //! Structs and enums used while parsing speaker data
 
-use std::fmt;
+use std::{fmt, net::Ipv4Addr, time::Duration};
+
+use crate::{alarm::AlarmRecurrence, errors::XMLError, time::parse_time_str};
 
 /// The track currently being played
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CurrentTrack {
     /// The current time of the track, in hh:mm:ss
     pub position: String,
@@ -15,10 +18,43 @@ pub struct CurrentTrack {
     pub title: Option<String>,
     /// The artist/creator of the track
     pub artist: Option<String>,
+    /// The album containing the track
+    pub album: Option<String>,
+    /// The URI of the album art for the track, usually relative to the speaker (see `Speaker::album_art_url`)
+    pub album_art_uri: Option<String>,
+    /// The genre of the track
+    pub genre: Option<String>,
+    /// The position of the track in the queue, starting at 1 (`None` if the speaker reports no track number)
+    ///
+    /// This is only meaningful while playing from the queue: other sources, such as radio streams and line-in, usually report track 1
+    pub track_number: Option<u32>,
+}
+
+impl CurrentTrack {
+    /// Returns the current time of the track as a `Duration`
+    pub fn position_as_duration(&self) -> Result<Duration, XMLError> {
+        parse_time_str(&self.position)
+    }
+
+    /// Returns the total length of the track as a `Duration`
+    pub fn duration_as_duration(&self) -> Result<Duration, XMLError> {
+        parse_time_str(&self.duration)
+    }
+}
+
+impl fmt::Display for CurrentTrack {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.artist, &self.title) {
+            (Some(artist), Some(title)) => write!(f, "{artist} - {title}")?,
+            _ => write!(f, "{}", self.uri)?,
+        }
+        write!(f, " ({} / {})", self.position, self.duration)
+    }
 }
 
 /// The current playback state of the speaker
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PlaybackState {
     /// Playback is stopped
     Stopped,
@@ -40,6 +76,26 @@ impl PlaybackState {
             _ => Err(String::from("Invalid playback state")),
         }
     }
+
+    /// Returns whether the track is playing
+    pub const fn is_playing(&self) -> bool {
+        matches!(self, Self::Playing)
+    }
+
+    /// Returns whether the track is paused
+    pub const fn is_paused(&self) -> bool {
+        matches!(self, Self::Paused)
+    }
+
+    /// Returns whether playback is stopped
+    pub const fn is_stopped(&self) -> bool {
+        matches!(self, Self::Stopped)
+    }
+
+    /// Returns whether the track is playing or about to play (transitioning usually means playback is starting)
+    pub const fn is_active(&self) -> bool {
+        matches!(self, Self::Playing | Self::Transitioning)
+    }
 }
 
 impl fmt::Display for PlaybackState {
@@ -56,6 +112,7 @@ impl fmt::Display for PlaybackState {
 
 /// Information about playback on the speaker
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PlaybackStatus {
     /// The current playback state on the speaker (playing, paused, etc...)
     pub playback_state: PlaybackState,
@@ -65,6 +122,7 @@ pub struct PlaybackStatus {
 
 /// A track in the queue
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct QueueItem {
     /// The length of the track, as hh:mm:ss
     pub duration: Option<String>,
@@ -74,4 +132,278 @@ pub struct QueueItem {
     pub title: Option<String>,
     /// The artist/creator of the track
     pub artist: Option<String>,
+    /// The album containing the track
+    pub album: Option<String>,
+    /// The URI of the album art for the track, usually relative to the speaker
+    pub album_art_uri: Option<String>,
+}
+
+impl QueueItem {
+    /// Returns the length of the track as a `Duration`, if the length is known
+    pub fn duration_as_duration(&self) -> Option<Result<Duration, XMLError>> {
+        self.duration.as_deref().map(parse_time_str)
+    }
+}
+
+impl fmt::Display for QueueItem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.artist, &self.title) {
+            (Some(artist), Some(title)) => write!(f, "{artist} - {title}")?,
+            _ => write!(f, "{}", self.uri)?,
+        }
+        match &self.duration {
+            Some(duration) => write!(f, " ({duration})"),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The shuffle and repeat mode of the speaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PlayMode {
+    /// Tracks play in order, without repeating
+    Normal,
+    /// Tracks play in a random order, repeating the queue when it ends (the name speakers normally report for this mode)
+    Shuffle,
+    /// Tracks play in a random order, without repeating
+    ShuffleNoRepeat,
+    /// Tracks play in order, repeating the queue when it ends
+    RepeatAll,
+    /// Plays the same way as `Shuffle`, but under the name some speakers report instead
+    ShuffleRepeatAll,
+    /// The current track repeats
+    RepeatOne,
+    /// Tracks play in a random order, and the current track repeats
+    ShuffleRepeatOne,
+}
+
+impl PlayMode {
+    pub(crate) fn new(mode_str: &str) -> Result<Self, String> {
+        match mode_str {
+            "NORMAL" => Ok(Self::Normal),
+            "SHUFFLE" => Ok(Self::Shuffle),
+            "SHUFFLE_NOREPEAT" => Ok(Self::ShuffleNoRepeat),
+            "REPEAT_ALL" => Ok(Self::RepeatAll),
+            "SHUFFLE_REPEAT_ALL" => Ok(Self::ShuffleRepeatAll),
+            "REPEAT_ONE" => Ok(Self::RepeatOne),
+            "SHUFFLE_REPEAT_ONE" => Ok(Self::ShuffleRepeatOne),
+            _ => Err(String::from("Invalid play mode")),
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            PlayMode::Normal => "NORMAL",
+            PlayMode::Shuffle => "SHUFFLE",
+            PlayMode::ShuffleNoRepeat => "SHUFFLE_NOREPEAT",
+            PlayMode::RepeatAll => "REPEAT_ALL",
+            PlayMode::ShuffleRepeatAll => "SHUFFLE_REPEAT_ALL",
+            PlayMode::RepeatOne => "REPEAT_ONE",
+            PlayMode::ShuffleRepeatOne => "SHUFFLE_REPEAT_ONE",
+        }
+    }
+}
+
+impl fmt::Display for PlayMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let output = match self {
+            PlayMode::Normal => "Normal",
+            PlayMode::Shuffle => "Shuffle",
+            PlayMode::ShuffleNoRepeat => "Shuffle (no repeat)",
+            PlayMode::RepeatAll => "Repeat all",
+            PlayMode::ShuffleRepeatAll => "Shuffle (repeat all)",
+            PlayMode::RepeatOne => "Repeat one",
+            PlayMode::ShuffleRepeatOne => "Shuffle (repeat one)",
+        };
+        write!(f, "{output}")
+    }
+}
+
+/// The transport settings of the speaker
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransportSettings {
+    /// The current shuffle and repeat mode of the speaker
+    pub play_mode: PlayMode,
+    /// The recording quality mode of the speaker
+    pub rec_quality_mode: String,
+}
+
+/// A record of what a speaker was playing, used to return the speaker to that state later
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlaybackSnapshot {
+    /// The URI of the source that was loaded (empty if nothing was loaded)
+    pub uri: String,
+    /// The DIDL-Lite metadata of the source that was loaded
+    pub metadata: String,
+    /// The position of the track in the queue, if playing from the queue
+    pub track_number: Option<u32>,
+    /// The position in the track, as hh:mm:ss (`None` for sources without a position, such as radio streams)
+    pub position: Option<String>,
+    /// The volume of the speaker
+    pub volume: u8,
+    /// Whether the speaker was muted
+    pub muted: bool,
+    /// The shuffle and repeat mode of the speaker
+    pub play_mode: PlayMode,
+    /// The playback state of the speaker
+    pub playback_state: PlaybackState,
+}
+
+/// A group of speakers that play the same audio
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ZoneGroup {
+    /// The UUID of the speaker leading the group
+    pub coordinator_uuid: String,
+    /// The speakers in the group, including the coordinator
+    pub members: Vec<ZoneMember>,
+}
+
+/// A speaker in a zone group
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ZoneMember {
+    /// The unique ID of the speaker
+    pub uuid: String,
+    /// The name of the room containing the speaker
+    pub room_name: String,
+    /// The IP address of the speaker
+    pub ip_addr: Ipv4Addr,
+    /// Whether the speaker leads its group
+    pub is_coordinator: bool,
+}
+
+/// An alarm configured on the speakers in a household
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Alarm {
+    /// The unique ID of the alarm
+    pub id: u32,
+    /// The time of day at which the alarm starts, as hh:mm:ss
+    pub start_time: String,
+    /// How long the alarm plays for, as hh:mm:ss
+    pub duration: String,
+    /// The days on which the alarm plays
+    pub recurrence: AlarmRecurrence,
+    /// Whether the alarm is enabled
+    pub enabled: bool,
+    /// The UUID of the speaker that plays the alarm
+    pub room_uuid: String,
+    /// The URI of the audio played by the alarm (`x-rincon-buzzer:0` for the built-in chime)
+    pub program_uri: String,
+    /// The DIDL-Lite metadata of the audio played by the alarm
+    pub program_metadata: String,
+    /// The shuffle and repeat mode used while the alarm plays
+    pub play_mode: PlayMode,
+    /// The volume at which the alarm plays
+    pub volume: u8,
+    /// Whether the alarm also plays on the other speakers grouped with the alarm's speaker
+    pub include_linked_zones: bool,
+}
+
+/// An item saved in Sonos favorites
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FavoriteItem {
+    /// The name of the favorite
+    pub title: String,
+    /// The source URI of the favorite
+    pub uri: String,
+    /// The DIDL-Lite metadata of the favorite, sent to the speaker when playing it
+    pub metadata: String,
+    /// The URI of the art for the favorite
+    pub album_art_uri: Option<String>,
+}
+
+/// A Sonos playlist, saved from the queue or created in the Sonos app
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlaylistItem {
+    /// The ID of the playlist, ex. `SQ:3`
+    pub id: String,
+    /// The name of the playlist
+    pub title: String,
+    /// The URI of the playlist, which can be added to the queue
+    pub uri: String,
+    /// The number of tracks in the playlist (0 if the speaker does not report it)
+    pub track_count: u32,
+}
+
+/// A radio station saved in the speaker's household
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RadioStation {
+    /// The name of the station
+    pub title: String,
+    /// The stream URI of the station, usually using the `x-sonosapi-stream:` scheme
+    pub uri: String,
+    /// The URI of the station's logo
+    pub logo_uri: Option<String>,
+}
+
+/// An item or container found while browsing the speaker's content
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ContentNode {
+    /// A playable item, such as a track or station
+    Item(ContentItem),
+    /// A container of other nodes, such as an album or playlist
+    Container(ContentContainer),
+}
+
+/// A playable item found while browsing the speaker's content
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContentItem {
+    /// The ID of the item
+    pub id: String,
+    /// The title of the item
+    pub title: String,
+    /// The source URI of the item (empty if the item has none)
+    pub uri: String,
+    /// The DIDL-Lite metadata to send to the speaker when playing the item (empty if the item has none)
+    pub metadata: String,
+    /// The URI of the art for the item
+    pub art_uri: Option<String>,
+}
+
+/// A container found while browsing the speaker's content, whose ID can be browsed for its children
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContentContainer {
+    /// The ID of the container
+    pub id: String,
+    /// The title of the container
+    pub title: String,
+    /// The URI of the container, which can be added to the queue (empty if the container has none)
+    pub uri: String,
+    /// The number of children in the container (0 if the speaker does not report it)
+    pub child_count: u32,
+}
+
+/// A page of nodes found while browsing the speaker's content
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BrowseResult {
+    /// The nodes in this page
+    pub items: Vec<ContentNode>,
+    /// The total number of nodes in the browsed container, across all pages
+    pub total_matches: u32,
+    /// The number of nodes in this page
+    pub number_returned: u32,
+}
+
+/// The result of adding tracks to the queue
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AddToQueueResult {
+    /// The position in the queue of the first track added, starting at 1
+    pub first_track_number_enqueued: u32,
+    /// The number of tracks added (more than 1 when adding a container, such as a playlist)
+    pub num_tracks_added: u32,
+    /// The number of tracks in the queue after adding
+    pub new_queue_length: u32,
 }
@@ -1,23 +1,43 @@
 pub enum Service {
+    AlarmClock,
     AVTransport,
     ContentDirectory,
+    DeviceProperties,
     RenderingControl,
+    ZoneGroupTopology,
 }
 
 impl Service {
     pub fn get_name(&self) -> &'static str {
         match self {
+            Service::AlarmClock => "AlarmClock:1",
             Service::AVTransport => "AVTransport:1",
             Service::ContentDirectory => "ContentDirectory:1",
+            Service::DeviceProperties => "DeviceProperties:1",
             Service::RenderingControl => "RenderingControl:1",
+            Service::ZoneGroupTopology => "ZoneGroupTopology:1",
         }
     }
 
     pub fn get_endpoint(&self) -> &'static str {
         match self {
+            Service::AlarmClock => "/AlarmClock/Control",
             Service::AVTransport => "/MediaRenderer/AVTransport/Control",
             Service::ContentDirectory => "/MediaServer/ContentDirectory/Control",
+            Service::DeviceProperties => "/DeviceProperties/Control",
             Service::RenderingControl => "/MediaRenderer/RenderingControl/Control",
+            Service::ZoneGroupTopology => "/ZoneGroupTopology/Control",
+        }
+    }
+
+    pub fn get_event_endpoint(&self) -> &'static str {
+        match self {
+            Service::AlarmClock => "/AlarmClock/Event",
+            Service::AVTransport => "/MediaRenderer/AVTransport/Event",
+            Service::ContentDirectory => "/MediaServer/ContentDirectory/Event",
+            Service::DeviceProperties => "/DeviceProperties/Event",
+            Service::RenderingControl => "/MediaRenderer/RenderingControl/Event",
+            Service::ZoneGroupTopology => "/ZoneGroupTopology/Event",
         }
     }
 }
@@ -1,26 +1,65 @@
 //! Resources for connecting to and controlling speakers
 
 use crate::{
-    discovery::get_speaker_info,
-    errors::{SonosError, SpeakerError},
-    responses::{CurrentTrack, PlaybackStatus, QueueItem},
+    alarm::AlarmConfig,
+    discovery::get_speaker_info_with_client,
+    errors::{SonosError, SpeakerError, XMLError},
+    events::{parse_timeout_header, EventSubscription},
+    group::SpeakerGroup,
+    metadata::DidlMetadata,
+    responses::{
+        AddToQueueResult, Alarm, BrowseResult, ContentNode, CurrentTrack, FavoriteItem, PlayMode,
+        PlaybackSnapshot, PlaybackStatus, PlaylistItem, QueueItem, RadioStation, TransportSettings,
+        ZoneGroup,
+    },
     services::Service,
+    time::duration_to_time_str,
     xml::{
-        generate_xml, get_error_code, parse_current_track_xml, parse_getvolume_xml,
-        parse_playback_status_xml, parse_queue_xml,
+        generate_xml, get_error_code, parse_add_to_queue_xml, parse_browse_update_xml,
+        parse_browse_xml, parse_create_alarm_xml, parse_crossfade_mode_xml,
+        parse_current_track_xml, parse_getbass_xml, parse_getbuttonlockstate_xml, parse_geteq_xml,
+        parse_getledstate_xml, parse_getloudness_xml, parse_getmute_xml, parse_gettreble_xml,
+        parse_getvolume_xml, parse_list_alarms_xml, parse_media_info_xml,
+        parse_playback_status_xml, parse_queue_xml, parse_save_queue_xml,
+        parse_setrelativevolume_xml, parse_total_matches_xml, parse_transport_settings_xml,
+        parse_zone_attributes_xml, parse_zone_group_state_xml,
     },
 };
 use reqwest::{self, StatusCode};
-use std::collections::HashMap;
+use std::error::Error;
+use std::future::Future;
+use std::hash::{Hash, Hasher};
 use std::net::Ipv4Addr;
+use std::time::{Duration, Instant};
+
+const NOTIFICATION_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// how long past the end of a notification clip to wait for the speaker to report that it has stopped
+const NOTIFICATION_END_MARGIN: Duration = Duration::from_secs(5);
+
+// how long to wait for a notification clip whose length the speaker does not report
+const NOTIFICATION_MAX_WAIT: Duration = Duration::from_secs(30);
+
+const MAX_RAMP_STEPS: u32 = 20;
+
+// speakers accept at most this many URIs in a single request to add tracks to the queue
+const MAX_URIS_PER_REQUEST: usize = 16;
+
+const QUEUE_URI_PREFIX: &str = "x-rincon-queue:";
 
 /// Represents typical speaker data
-#[derive(Debug)]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BasicSpeakerInfo {
     pub(crate) ip_addr: Ipv4Addr,
     pub(crate) friendly_name: String,
     pub(crate) room_name: String,
     pub(crate) uuid: String,
+    pub(crate) model_name: String,
+    pub(crate) model_number: String,
+    pub(crate) software_version: String,
+    pub(crate) hardware_version: String,
+    pub(crate) household_id: String,
 }
 
 impl BasicSpeakerInfo {
@@ -43,35 +82,105 @@ impl BasicSpeakerInfo {
     pub fn uuid(&self) -> &str {
         &self.uuid
     }
+
+    /// The model of the speaker, ex. `Sonos Play:5`
+    pub fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    /// The model number of the speaker, ex. `S12`
+    pub fn model_number(&self) -> &str {
+        &self.model_number
+    }
+
+    /// The version of the software (firmware) running on the speaker
+    pub fn software_version(&self) -> &str {
+        &self.software_version
+    }
+
+    /// The hardware version of the speaker
+    pub fn hardware_version(&self) -> &str {
+        &self.hardware_version
+    }
+
+    /// The ID of the household the speaker belongs to (speakers can only interact with others in the same household)
+    ///
+    /// This is empty if the speaker does not report a household
+    pub fn household_id(&self) -> &str {
+        &self.household_id
+    }
 }
 
 impl PartialEq for BasicSpeakerInfo {
     fn eq(&self, other: &Self) -> bool {
-        self.ip_addr == other.ip_addr
+        self.uuid == other.uuid
+    }
+}
+
+impl Eq for BasicSpeakerInfo {}
+
+impl Hash for BasicSpeakerInfo {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.uuid.hash(state);
     }
 }
 
 /// A sonos speaker
+///
+/// Clones share the same HTTP client, and therefore the same connection pool, as the original
+#[derive(Clone)]
 pub struct Speaker {
     ip_addr: Ipv4Addr,
-    uuid: String,
+    pub(crate) uuid: String,
     friendly_name: String,
+    pub(crate) room_name: String,
+    model_name: String,
+    household_id: String,
     client: reqwest::Client,
 }
 
 impl Speaker {
     /// Creates a new speaker object, if a speaker is found at the specified IP address
     pub async fn new(ip_addr: Ipv4Addr) -> Result<Self, SpeakerError> {
-        let speaker = get_speaker_info(ip_addr).await?;
-
         let client = reqwest::Client::new();
 
-        Ok(Speaker {
-            ip_addr,
-            uuid: speaker.uuid,
-            friendly_name: speaker.friendly_name,
+        Self::new_with_client(ip_addr, client).await
+    }
+
+    /// Creates a new speaker object that sends requests using the given client, if a speaker is found at the specified IP address
+    ///
+    /// `reqwest::Client` is cheap to clone and shares its connection pool between clones,
+    /// so passing clones of one client to many speakers avoids creating a pool for each of them
+    pub async fn new_with_client(
+        ip_addr: Ipv4Addr,
+        client: reqwest::Client,
+    ) -> Result<Self, SpeakerError> {
+        let speaker = get_speaker_info_with_client(&client, ip_addr).await?;
+
+        Ok(Self::from_basic_info_with_client(speaker, client))
+    }
+
+    /// Creates a new speaker object from already fetched speaker information, without making any requests
+    ///
+    /// * `info` - information about the speaker, as returned by `discover_devices` or `get_speaker_info`
+    pub fn from_basic_info(info: BasicSpeakerInfo) -> Self {
+        Self::from_basic_info_with_client(info, reqwest::Client::new())
+    }
+
+    /// Creates a new speaker object from already fetched speaker information that sends requests using the given client
+    ///
+    /// * `info` - information about the speaker, as returned by `discover_devices` or `get_speaker_info`
+    /// * `client` - the client to send requests with (see `new_with_client`)
+    pub fn from_basic_info_with_client(info: BasicSpeakerInfo, client: reqwest::Client) -> Self {
+        Speaker {
+            ip_addr: info.ip_addr,
+            uuid: info.uuid,
+            friendly_name: info.friendly_name,
+            room_name: info.room_name,
+            model_name: info.model_name,
+            household_id: info.household_id,
             client,
-        })
+        }
     }
 
     /// Returns the ID of the speaker
@@ -89,11 +198,35 @@ impl Speaker {
         self.friendly_name.to_owned()
     }
 
+    /// Returns the model of the speaker, ex. `Sonos Play:5`
+    pub fn get_model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    /// Returns the ID of the household the speaker belongs to, or an empty string if the speaker does not report one
+    pub fn get_household_id(&self) -> &str {
+        &self.household_id
+    }
+
+    /// Returns the version of the software (firmware) currently running on the speaker
+    ///
+    /// This is fetched from the speaker each time, since the speaker may have updated since this object was created
+    pub async fn get_firmware_version(&self) -> Result<String, SpeakerError> {
+        let speaker = get_speaker_info_with_client(&self.client, self.ip_addr).await?;
+
+        Ok(speaker.software_version)
+    }
+
+    /// Returns the name of the room containing the speaker
+    pub fn get_room_name(&self) -> String {
+        self.room_name.to_owned()
+    }
+
     async fn make_request(
         &self,
         service: Service,
         action_name: &str,
-        arguments: HashMap<&str, &str>,
+        arguments: &[(&str, &str)],
     ) -> Result<String, SpeakerError> {
         let url = format!("http://{}:1400{}", self.ip_addr, service.get_endpoint());
 
@@ -121,12 +254,44 @@ impl Speaker {
         if let StatusCode::OK = status {
             Ok(xml_response)
         } else {
-            let error_code = get_error_code(xml_response)?;
+            // responses that are not UPnP errors, such as from an overloaded speaker, have no error code
+            let Ok(error_code) = get_error_code(xml_response) else {
+                return Err(SpeakerError::HttpError(status));
+            };
+            let additional_details = format!("HTTP status code: {}", status);
 
-            Err(SpeakerError::from(SonosError::from_err_code(
-                &error_code,
-                &format!("HTTP status code: {}", status),
-            )))
+            let error =
+                SonosError::from_service_err_code(&service, &error_code, &additional_details);
+
+            Err(SpeakerError::from(error))
+        }
+    }
+
+    /// Sends a UPnP eventing request (`SUBSCRIBE` or `UNSUBSCRIBE`) to one of the speaker's event endpoints
+    pub(crate) async fn send_event_request(
+        &self,
+        method: &str,
+        event_endpoint: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<reqwest::Response, SpeakerError> {
+        let url = format!("http://{}:1400{}", self.ip_addr, event_endpoint);
+
+        let method = reqwest::Method::from_bytes(method.as_bytes())
+            .map_err(|_| SpeakerError::InvalidInput(format!("invalid method: {}", method)))?;
+
+        let mut request = self.client.request(method, url);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+
+        let response = request.send().await?;
+
+        let status = response.status();
+
+        if let StatusCode::OK = status {
+            Ok(response)
+        } else {
+            Err(SpeakerError::HttpError(status))
         }
     }
 
@@ -135,9 +300,7 @@ impl Speaker {
         let action_name = "Play";
         let service = Service::AVTransport;
 
-        let mut arguments = HashMap::new();
-        arguments.insert("InstanceID", "0");
-        arguments.insert("Speed", "1");
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0"), ("Speed", "1")];
 
         let _ = self.make_request(service, action_name, arguments).await?;
 
@@ -149,8 +312,19 @@ impl Speaker {
         let action_name = "Pause";
         let service = Service::AVTransport;
 
-        let mut arguments = HashMap::new();
-        arguments.insert("InstanceID", "0");
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0")];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Stops playback on the speaker
+    pub async fn stop(&self) -> Result<(), SpeakerError> {
+        let action_name = "Stop";
+        let service = Service::AVTransport;
+
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0")];
 
         let _ = self.make_request(service, action_name, arguments).await?;
 
@@ -162,8 +336,7 @@ impl Speaker {
         let action_name = "GetPositionInfo";
         let service = Service::AVTransport;
 
-        let mut arguments = HashMap::new();
-        arguments.insert("InstanceID", "0");
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0")];
 
         let xml_response = self.make_request(service, action_name, arguments).await?;
 
@@ -172,31 +345,293 @@ impl Speaker {
         Ok(current_track)
     }
 
+    /// Returns the full URL of the album art for a track, which can be fetched directly
+    ///
+    /// * `track` - the track to get the album art URL for, as returned by `get_current_track`
+    pub fn album_art_url(&self, track: &CurrentTrack) -> Option<String> {
+        let album_art_uri = track.album_art_uri.as_ref()?;
+
+        if album_art_uri.starts_with("http://") || album_art_uri.starts_with("https://") {
+            Some(album_art_uri.to_owned())
+        } else {
+            Some(format!("http://{}:1400{}", self.ip_addr, album_art_uri))
+        }
+    }
+
     /// Sets the current track source to the given URI
     ///
     /// * `uri` - the URI of to the audio file to play
     pub async fn set_current_uri(&self, uri: &str) -> Result<(), SpeakerError> {
+        self.set_av_transport_uri(uri, "").await
+    }
+
+    /// Sets the source to play after the current track finishes, so that the speaker can switch to it without a gap
+    ///
+    /// * `uri` - the URI of the audio file to play next
+    /// * `metadata` - DIDL-Lite metadata describing the audio file, or an empty string
+    pub async fn set_next_uri(&self, uri: &str, metadata: &str) -> Result<(), SpeakerError> {
+        let action_name = "SetNextAVTransportURI";
+        let service = Service::AVTransport;
+
+        let arguments: &[(&str, &str)] = &[
+            ("InstanceID", "0"),
+            ("NextURI", uri),
+            ("NextURIMetaData", metadata),
+        ];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Sets the current track source to the given URI, along with metadata describing it (shown in Sonos apps)
+    ///
+    /// * `uri` - the URI of to the audio file to play
+    /// * `metadata` - information about the audio file, such as its title and artist
+    pub async fn set_uri_with_metadata(
+        &self,
+        uri: &str,
+        metadata: &DidlMetadata,
+    ) -> Result<(), SpeakerError> {
+        let metadata = metadata.to_xml_string()?;
+
+        self.set_av_transport_uri(uri, &metadata).await
+    }
+
+    async fn set_av_transport_uri(&self, uri: &str, metadata: &str) -> Result<(), SpeakerError> {
         let action_name = "SetAVTransportURI";
         let service = Service::AVTransport;
 
-        let mut arguments = HashMap::new();
-        arguments.insert("InstanceID", "0");
-        arguments.insert("CurrentURI", uri);
-        arguments.insert("CurrentURIMetaData", "");
+        let arguments: &[(&str, &str)] = &[
+            ("InstanceID", "0"),
+            ("CurrentURI", uri),
+            ("CurrentURIMetaData", metadata),
+        ];
 
         let _ = self.make_request(service, action_name, arguments).await?;
 
         Ok(())
     }
 
+    async fn get_media_info(&self) -> Result<(String, String), SpeakerError> {
+        let action_name = "GetMediaInfo";
+        let service = Service::AVTransport;
+
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0")];
+
+        let xml_response = self.make_request(service, action_name, arguments).await?;
+
+        parse_media_info_xml(xml_response).map_err(SpeakerError::from)
+    }
+
+    /// Records what the speaker is currently playing, so that it can be restored later with `restore_snapshot`
+    pub async fn snapshot(&self) -> Result<PlaybackSnapshot, SpeakerError> {
+        let playback_state = self.get_playback_status().await?.playback_state;
+        let (uri, metadata) = self.get_media_info().await?;
+        let current_track = self.get_current_track().await?;
+        let volume = self.get_volume().await?;
+        let muted = self.get_mute().await?;
+        let play_mode = self.get_play_mode().await?;
+
+        // streams report their position as NOT_IMPLEMENTED, and cannot be seeked
+        let position = Some(current_track.position)
+            .filter(|position| position.contains(':') && position != "0:00:00");
+
+        Ok(PlaybackSnapshot {
+            uri,
+            metadata,
+            track_number: current_track.track_number,
+            position,
+            volume,
+            muted,
+            play_mode,
+            playback_state,
+        })
+    }
+
+    /// Returns the speaker to the state recorded in a snapshot
+    ///
+    /// * `snapshot` - the snapshot to restore, as returned by `snapshot`
+    ///
+    /// Playback resumes only if the speaker was playing when the snapshot was taken.
+    /// The play mode, track and position are only restored if the speaker was playing from the queue
+    pub async fn restore_snapshot(&self, snapshot: PlaybackSnapshot) -> Result<(), SpeakerError> {
+        self.set_volume(snapshot.volume).await?;
+        self.set_mute(snapshot.muted).await?;
+
+        if snapshot.uri.is_empty() {
+            // nothing was loaded when the snapshot was taken, so there is nothing to go back to
+            return Ok(());
+        }
+
+        self.set_av_transport_uri(&snapshot.uri, &snapshot.metadata)
+            .await?;
+
+        // play modes, track numbers and positions only apply to the queue, and other sources such as streams reject them
+        if snapshot.uri.starts_with(QUEUE_URI_PREFIX) {
+            self.set_play_mode(snapshot.play_mode).await?;
+
+            if let Some(track_number) = snapshot.track_number {
+                self.seek_to_track(track_number).await?;
+            }
+
+            if let Some(position) = &snapshot.position {
+                self.seek(position).await?;
+            }
+        }
+
+        if snapshot.playback_state.is_active() {
+            self.play().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Interrupts playback to play an audio clip, then returns the speaker to what it was doing before
+    ///
+    /// * `clip_uri` - the URI of the audio clip to play
+    /// * `volume` - the volume to play the clip at, or `None` to use the current volume
+    ///
+    /// The speaker is unmuted while the clip plays. This waits until the clip has finished playing, for at most the clip's length plus a few seconds
+    /// (or 30 seconds if the speaker does not report its length), so a clip that does not end on its own, such as a stream, is cut off.
+    /// Afterwards, the previous state is restored as with `restore_snapshot`, so playback resumes only if the speaker was playing before.
+    /// If the previous source had no position (such as a radio stream), it restarts from its live position.
+    /// The previous state is restored even if playing the clip fails, in which case the error from playing the clip is returned
+    pub async fn play_notification(
+        &self,
+        clip_uri: &str,
+        volume: Option<u8>,
+    ) -> Result<(), SpeakerError> {
+        let snapshot = self.snapshot().await?;
+
+        // the previous state is restored even if playing the clip fails, so the speaker is not left on the clip
+        let played = self.play_clip(clip_uri, volume).await;
+        let restored = self.restore_snapshot(snapshot).await;
+
+        played.and(restored)
+    }
+
+    async fn play_clip(&self, clip_uri: &str, volume: Option<u8>) -> Result<(), SpeakerError> {
+        self.set_current_uri(clip_uri).await?;
+        if let Some(volume) = volume {
+            self.set_volume(volume).await?;
+        }
+        // the previous mute state is restored along with the rest of the snapshot
+        self.set_mute(false).await?;
+        self.play().await?;
+
+        // the speaker may never report that the clip has stopped (ex. if it is a stream), so the wait is limited by the clip's length
+        let clip_duration = self
+            .get_current_track()
+            .await?
+            .duration_as_duration()
+            .unwrap_or_default();
+
+        let max_wait = if clip_duration.is_zero() {
+            NOTIFICATION_MAX_WAIT
+        } else {
+            clip_duration + NOTIFICATION_END_MARGIN
+        };
+
+        let deadline = Instant::now() + max_wait;
+
+        while Instant::now() < deadline {
+            tokio::time::sleep(NOTIFICATION_POLL_INTERVAL).await;
+
+            if !self.get_playback_status().await?.playback_state.is_active() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Returns the current volume of the speaker
     pub async fn get_volume(&self) -> Result<u8, SpeakerError> {
+        self.get_channel_volume("Master").await
+    }
+
+    /// Changes the volume of the speaker to the specified value
+    ///
+    /// * `new_volume` - the volume to set the speaker to, between 0 and 100 inclusive
+    pub async fn set_volume(&self, new_volume: u8) -> Result<(), SpeakerError> {
+        self.set_channel_volume("Master", new_volume).await
+    }
+
+    /// Gradually changes the volume of the speaker to the specified value over the given duration
+    ///
+    /// * `target` - the volume to end at, between 0 and 100 inclusive
+    /// * `duration` - how long the change should take
+    ///
+    /// This is a best-effort ramp made of many small volume changes (at most 20, each by at least 1),
+    /// not a fade performed by the speaker itself, so it may take longer than `duration` on a slow network
+    pub async fn ramp_volume(&self, target: u8, duration: Duration) -> Result<(), SpeakerError> {
+        if target > 100 {
+            return Err(SpeakerError::InvalidInput(format!(
+                "invalid volume: {}",
+                target
+            )));
+        };
+
+        let start = i32::from(self.get_volume().await?);
+        let difference = i32::from(target) - start;
+
+        let steps = difference.unsigned_abs().min(MAX_RAMP_STEPS);
+
+        if steps == 0 {
+            return Ok(());
+        }
+
+        let interval = duration / steps;
+
+        for step in 1..=steps {
+            tokio::time::sleep(interval).await;
+
+            let volume = start + difference * step as i32 / steps as i32;
+
+            self.set_volume(volume as u8).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the balance between the left and right channels of the speaker,
+    /// from -100 (fully left) to 100 (fully right), where 0 is centered
+    pub async fn get_balance(&self) -> Result<i8, SpeakerError> {
+        let left = self.get_channel_volume("LF").await?;
+        let right = self.get_channel_volume("RF").await?;
+
+        Ok((i16::from(right) - i16::from(left)) as i8)
+    }
+
+    /// Changes the balance between the left and right channels of the speaker
+    ///
+    /// * `balance` - the balance to set the speaker to, from -100 (fully left) to 100 (fully right), where 0 is centered
+    ///
+    /// This only affects speakers that play in stereo, such as stereo pairs
+    pub async fn set_balance(&self, balance: i8) -> Result<(), SpeakerError> {
+        if !(-100..=100).contains(&balance) {
+            return Err(SpeakerError::InvalidInput(format!(
+                "invalid balance: {}",
+                balance
+            )));
+        };
+
+        // the channel on the side being favored stays at full volume, while the other is lowered
+        let left = (100 - balance.max(0)) as u8;
+        let right = (100 + balance.min(0)) as u8;
+
+        self.set_channel_volume("LF", left).await?;
+        self.set_channel_volume("RF", right).await?;
+
+        Ok(())
+    }
+
+    async fn get_channel_volume(&self, channel: &str) -> Result<u8, SpeakerError> {
         let action_name = "GetVolume";
         let service = Service::RenderingControl;
 
-        let mut arguments = HashMap::new();
-        arguments.insert("InstanceID", "0");
-        arguments.insert("Channel", "Master");
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0"), ("Channel", channel)];
 
         let xml_response = self.make_request(service, action_name, arguments).await?;
 
@@ -205,10 +640,7 @@ impl Speaker {
         Ok(volume)
     }
 
-    /// Changes the volume of the speaker to the specified value
-    ///
-    /// * `new_volume` - the volume to set the speaker to, between 0 and 100 inclusive
-    pub async fn set_volume(&self, new_volume: u8) -> Result<(), SpeakerError> {
+    async fn set_channel_volume(&self, channel: &str, new_volume: u8) -> Result<(), SpeakerError> {
         if new_volume > 100 {
             return Err(SpeakerError::InvalidInput(format!(
                 "invalid volume: {}",
@@ -221,143 +653,1681 @@ impl Speaker {
         let action_name = "SetVolume";
         let service = Service::RenderingControl;
 
-        let mut arguments = HashMap::new();
-        arguments.insert("InstanceID", "0");
-        arguments.insert("Channel", "Master");
-        arguments.insert("DesiredVolume", &new_volume);
+        let arguments: &[(&str, &str)] = &[
+            ("InstanceID", "0"),
+            ("Channel", channel),
+            ("DesiredVolume", &new_volume),
+        ];
 
         let _ = self.make_request(service, action_name, arguments).await?;
 
         Ok(())
     }
 
-    /// Returns the current status of playback on the speaker (playing, paused, stopped, etc...)
-    pub async fn get_playback_status(&self) -> Result<PlaybackStatus, SpeakerError> {
-        let action_name = "GetTransportInfo";
-        let service = Service::AVTransport;
+    /// Changes the volume of the speaker by the given amount, returning the new volume
+    ///
+    /// * `delta` - the amount to change the volume by, negative to turn the volume down
+    ///
+    /// The new volume is clamped to between 0 and 100 inclusive, so changes that would
+    /// go past either end set the volume to that end rather than returning an error
+    pub async fn set_relative_volume(&self, delta: i8) -> Result<u8, SpeakerError> {
+        let delta = delta.to_string();
+
+        let action_name = "SetRelativeVolume";
+        let service = Service::RenderingControl;
 
-        let mut arguments = HashMap::new();
-        arguments.insert("InstanceID", "0");
+        let arguments: &[(&str, &str)] = &[
+            ("InstanceID", "0"),
+            ("Channel", "Master"),
+            ("Adjustment", &delta),
+        ];
 
         let xml_response = self.make_request(service, action_name, arguments).await?;
 
-        parse_playback_status_xml(xml_response).map_err(SpeakerError::from)
+        parse_setrelativevolume_xml(xml_response).map_err(SpeakerError::from)
     }
 
-    /// Starts playing from the specified position in the current track
-    ///
-    /// * `new_position` - the position to start playing from, as hh:mm:ss
-    pub async fn seek(&self, new_position: &str) -> Result<(), SpeakerError> {
-        let action_name = "Seek";
-        let service = Service::AVTransport;
+    /// Returns whether the speaker is currently muted
+    pub async fn get_mute(&self) -> Result<bool, SpeakerError> {
+        let action_name = "GetMute";
+        let service = Service::RenderingControl;
 
-        let mut arguments = HashMap::new();
-        arguments.insert("InstanceID", "0");
-        arguments.insert("Unit", "REL_TIME");
-        arguments.insert("Target", new_position);
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0"), ("Channel", "Master")];
 
-        let _ = self.make_request(service, action_name, arguments).await?;
+        let xml_response = self.make_request(service, action_name, arguments).await?;
 
-        Ok(())
+        parse_getmute_xml(xml_response).map_err(SpeakerError::from)
     }
 
-    /// Returns all tracks in the queue
-    pub async fn get_queue(&self) -> Result<Vec<QueueItem>, SpeakerError> {
-        let action_name = "Browse";
-        let service = Service::ContentDirectory;
-
-        let mut arguments = HashMap::new();
-        arguments.insert("ObjectID", "Q:0");
-        arguments.insert("BrowseFlag", "BrowseDirectChildren");
-        arguments.insert("Filter", "*");
-        arguments.insert("StartingIndex", "0");
-        arguments.insert("RequestedCount", "100");
-        arguments.insert("SortCriteria", "");
-
-        let xml_response = self.make_request(service, action_name, arguments).await?;
+    /// Mutes the speaker, without changing its volume
+    pub async fn mute(&self) -> Result<(), SpeakerError> {
+        self.set_mute(true).await
+    }
 
-        parse_queue_xml(xml_response).map_err(SpeakerError::from)
+    /// Unmutes the speaker, restoring playback at its current volume
+    pub async fn unmute(&self) -> Result<(), SpeakerError> {
+        self.set_mute(false).await
     }
 
-    /// Start playback from the queue (you must enter the queue before playing tracks from it)
-    pub async fn enter_queue(&self) -> Result<(), SpeakerError> {
-        let queue_uri = format!("x-rincon-queue:{}#0", &self.uuid);
-        self.set_current_uri(&queue_uri).await?;
+    /// Mutes the speaker if it is unmuted, or unmutes it if it is muted, returning whether the speaker is now muted
+    pub async fn toggle_mute(&self) -> Result<bool, SpeakerError> {
+        let muted = !self.get_mute().await?;
 
-        Ok(())
+        self.set_mute(muted).await?;
+
+        Ok(muted)
     }
 
-    /// Add a track to the end of the queue
-    ///
-    /// * `uri` - the URI of the track to add
-    pub async fn add_track_to_queue(&self, uri: &str) -> Result<(), SpeakerError> {
-        let action_name = "AddURIToQueue";
-        let service = Service::AVTransport;
+    async fn set_mute(&self, muted: bool) -> Result<(), SpeakerError> {
+        let action_name = "SetMute";
+        let service = Service::RenderingControl;
 
-        let mut arguments = HashMap::new();
-        arguments.insert("InstanceID", "0");
-        arguments.insert("EnqueuedURI", uri);
-        arguments.insert("EnqueuedURIMetaData", "");
-        arguments.insert("DesiredFirstTrackNumberEnqueued", "0");
-        arguments.insert("EnqueueAsNext", "0");
+        let arguments: &[(&str, &str)] = &[
+            ("InstanceID", "0"),
+            ("Channel", "Master"),
+            ("DesiredMute", if muted { "1" } else { "0" }),
+        ];
 
         let _ = self.make_request(service, action_name, arguments).await?;
 
         Ok(())
     }
 
-    /// Skips to the next track in the queue, erroring if there are no tracks after the current one
-    /// Note: this function will error if you use it before you have entered the queue
-    pub async fn move_to_next_track(&self) -> Result<(), SpeakerError> {
-        let action_name = "Next";
-        let service = Service::AVTransport;
+    /// Returns the current bass level of the speaker, between -10 and 10 inclusive
+    pub async fn get_bass(&self) -> Result<i8, SpeakerError> {
+        let action_name = "GetBass";
+        let service = Service::RenderingControl;
 
-        let mut arguments = HashMap::new();
-        arguments.insert("InstanceID", "0");
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0")];
 
-        let _ = self.make_request(service, action_name, arguments).await?;
+        let xml_response = self.make_request(service, action_name, arguments).await?;
 
-        Ok(())
+        parse_getbass_xml(xml_response).map_err(SpeakerError::from)
     }
 
-    /// Goes back to the previous track in the queue, erroring if there are no tracks before the current one
-    /// Note: this function will error if you use it before you have entered the queue
-    pub async fn move_to_previous_track(&self) -> Result<(), SpeakerError> {
-        let action_name = "Previous";
-        let service = Service::AVTransport;
+    /// Changes the bass level of the speaker to the specified value
+    ///
+    /// * `value` - the bass level to set the speaker to, between -10 and 10 inclusive
+    pub async fn set_bass(&self, value: i8) -> Result<(), SpeakerError> {
+        self.set_tone("Bass", value).await
+    }
 
-        let mut arguments = HashMap::new();
-        arguments.insert("InstanceID", "0");
+    /// Returns the current treble level of the speaker, between -10 and 10 inclusive
+    pub async fn get_treble(&self) -> Result<i8, SpeakerError> {
+        let action_name = "GetTreble";
+        let service = Service::RenderingControl;
 
-        let _ = self.make_request(service, action_name, arguments).await?;
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0")];
 
-        Ok(())
+        let xml_response = self.make_request(service, action_name, arguments).await?;
+
+        parse_gettreble_xml(xml_response).map_err(SpeakerError::from)
     }
 
-    /// Clears all tracks from the queue
-    pub async fn clear_queue(&self) -> Result<(), SpeakerError> {
-        let action_name = "RemoveAllTracksFromQueue";
-        let service = Service::AVTransport;
+    /// Changes the treble level of the speaker to the specified value
+    ///
+    /// * `value` - the treble level to set the speaker to, between -10 and 10 inclusive
+    pub async fn set_treble(&self, value: i8) -> Result<(), SpeakerError> {
+        self.set_tone("Treble", value).await
+    }
 
-        let mut arguments = HashMap::new();
-        arguments.insert("InstanceID", "0");
+    /// Returns whether loudness is enabled on the speaker (loudness boosts bass and treble at low volumes)
+    pub async fn get_loudness(&self) -> Result<bool, SpeakerError> {
+        let action_name = "GetLoudness";
+        let service = Service::RenderingControl;
 
-        let _ = self.make_request(service, action_name, arguments).await?;
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0"), ("Channel", "Master")];
 
-        Ok(())
+        let xml_response = self.make_request(service, action_name, arguments).await?;
+
+        parse_getloudness_xml(xml_response).map_err(SpeakerError::from)
     }
 
-    /// Cuts off connections from any third party services trying to use the speaker
-    /// (Use this to stop playback from Spotify, for example)
-    pub async fn end_external_control(&self) -> Result<(), SpeakerError> {
-        let action_name = "EndDirectControlSession";
-        let service = Service::AVTransport;
+    /// Enables or disables loudness on the speaker
+    ///
+    /// * `enabled` - whether loudness should be enabled
+    pub async fn set_loudness(&self, enabled: bool) -> Result<(), SpeakerError> {
+        let action_name = "SetLoudness";
+        let service = Service::RenderingControl;
 
-        let mut arguments = HashMap::new();
-        arguments.insert("InstanceID", "0");
+        let arguments: &[(&str, &str)] = &[
+            ("InstanceID", "0"),
+            ("Channel", "Master"),
+            ("DesiredLoudness", if enabled { "1" } else { "0" }),
+        ];
 
         let _ = self.make_request(service, action_name, arguments).await?;
 
         Ok(())
     }
+
+    async fn set_tone(&self, tone: &str, value: i8) -> Result<(), SpeakerError> {
+        if !(-10..=10).contains(&value) {
+            return Err(SpeakerError::InvalidInput(format!(
+                "invalid {}: {}",
+                tone.to_lowercase(),
+                value
+            )));
+        };
+
+        let value = value.to_string();
+
+        let action_name = format!("Set{}", tone);
+        let service = Service::RenderingControl;
+
+        let argument_name = format!("Desired{}", tone);
+
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0"), (argument_name.as_str(), &value)];
+
+        let _ = self.make_request(service, &action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Returns whether night mode is enabled on the speaker (night mode reduces the volume of loud sounds)
+    ///
+    /// Note: night mode is only supported by soundbars, such as the Arc and Beam, and this will error on other speakers
+    pub async fn get_night_mode(&self) -> Result<bool, SpeakerError> {
+        Ok(self.get_eq("NightMode").await? != 0)
+    }
+
+    /// Enables or disables night mode on the speaker
+    ///
+    /// * `enabled` - whether night mode should be enabled
+    ///
+    /// Note: night mode is only supported by soundbars, such as the Arc and Beam, and this will error on other speakers
+    pub async fn set_night_mode(&self, enabled: bool) -> Result<(), SpeakerError> {
+        self.set_eq("NightMode", i8::from(enabled)).await
+    }
+
+    /// Returns whether speech enhancement is enabled on the speaker (speech enhancement makes dialog clearer)
+    ///
+    /// Note: speech enhancement is only supported by soundbars, such as the Arc and Beam, and this will error on other speakers
+    pub async fn get_speech_enhancement(&self) -> Result<bool, SpeakerError> {
+        Ok(self.get_eq("DialogLevel").await? != 0)
+    }
+
+    /// Enables or disables speech enhancement on the speaker
+    ///
+    /// * `enabled` - whether speech enhancement should be enabled
+    ///
+    /// Note: speech enhancement is only supported by soundbars, such as the Arc and Beam, and this will error on other speakers
+    pub async fn set_speech_enhancement(&self, enabled: bool) -> Result<(), SpeakerError> {
+        self.set_eq("DialogLevel", i8::from(enabled)).await
+    }
+
+    /// Returns the volume level of the surround speakers, between -15 and 15 inclusive
+    ///
+    /// Note: this is only supported by home theater setups with paired surround speakers,
+    /// and will return a `SonosError` from the speaker otherwise
+    pub async fn get_surround_level(&self) -> Result<i8, SpeakerError> {
+        self.get_eq("SurroundLevel").await
+    }
+
+    /// Changes the volume level of the surround speakers to the specified value
+    ///
+    /// * `level` - the level to set the surround speakers to, between -15 and 15 inclusive
+    ///
+    /// Note: this is only supported by home theater setups with paired surround speakers,
+    /// and will return a `SonosError` from the speaker otherwise
+    pub async fn set_surround_level(&self, level: i8) -> Result<(), SpeakerError> {
+        if !(-15..=15).contains(&level) {
+            return Err(SpeakerError::InvalidInput(format!(
+                "invalid surround level: {}",
+                level
+            )));
+        };
+
+        self.set_eq("SurroundLevel", level).await
+    }
+
+    /// Returns the volume level of the subwoofer, between -15 and 15 inclusive
+    ///
+    /// Note: this is only supported by speakers with a paired subwoofer,
+    /// and will return a `SonosError` from the speaker otherwise
+    pub async fn get_sub_gain(&self) -> Result<i8, SpeakerError> {
+        self.get_eq("SubGain").await
+    }
+
+    /// Changes the volume level of the subwoofer to the specified value
+    ///
+    /// * `gain` - the level to set the subwoofer to, between -15 and 15 inclusive
+    ///
+    /// Note: this is only supported by speakers with a paired subwoofer,
+    /// and will return a `SonosError` from the speaker otherwise
+    pub async fn set_sub_gain(&self, gain: i8) -> Result<(), SpeakerError> {
+        if !(-15..=15).contains(&gain) {
+            return Err(SpeakerError::InvalidInput(format!(
+                "invalid sub gain: {}",
+                gain
+            )));
+        };
+
+        self.set_eq("SubGain", gain).await
+    }
+
+    /// Returns whether the subwoofer is enabled
+    ///
+    /// Note: this is only supported by speakers with a paired subwoofer,
+    /// and will return a `SonosError` from the speaker otherwise
+    pub async fn get_sub_enabled(&self) -> Result<bool, SpeakerError> {
+        Ok(self.get_eq("SubEnabled").await? != 0)
+    }
+
+    /// Enables or disables the subwoofer
+    ///
+    /// * `enabled` - whether the subwoofer should be enabled
+    ///
+    /// Note: this is only supported by speakers with a paired subwoofer,
+    /// and will return a `SonosError` from the speaker otherwise
+    pub async fn set_sub_enabled(&self, enabled: bool) -> Result<(), SpeakerError> {
+        self.set_eq("SubEnabled", i8::from(enabled)).await
+    }
+
+    async fn get_eq(&self, eq_type: &str) -> Result<i8, SpeakerError> {
+        let action_name = "GetEQ";
+        let service = Service::RenderingControl;
+
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0"), ("EQType", eq_type)];
+
+        let xml_response = self.make_request(service, action_name, arguments).await?;
+
+        parse_geteq_xml(xml_response).map_err(SpeakerError::from)
+    }
+
+    async fn set_eq(&self, eq_type: &str, value: i8) -> Result<(), SpeakerError> {
+        let value = value.to_string();
+
+        let action_name = "SetEQ";
+        let service = Service::RenderingControl;
+
+        let arguments: &[(&str, &str)] = &[
+            ("InstanceID", "0"),
+            ("EQType", eq_type),
+            ("DesiredValue", &value),
+        ];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Returns the current status of playback on the speaker (playing, paused, stopped, etc...)
+    pub async fn get_playback_status(&self) -> Result<PlaybackStatus, SpeakerError> {
+        let action_name = "GetTransportInfo";
+        let service = Service::AVTransport;
+
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0")];
+
+        let xml_response = self.make_request(service, action_name, arguments).await?;
+
+        parse_playback_status_xml(xml_response).map_err(SpeakerError::from)
+    }
+
+    /// Returns the current transport settings of the speaker, including the play mode
+    pub async fn get_transport_settings(&self) -> Result<TransportSettings, SpeakerError> {
+        let action_name = "GetTransportSettings";
+        let service = Service::AVTransport;
+
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0")];
+
+        let xml_response = self.make_request(service, action_name, arguments).await?;
+
+        parse_transport_settings_xml(xml_response).map_err(SpeakerError::from)
+    }
+
+    /// Returns the current shuffle and repeat mode of the speaker
+    pub async fn get_play_mode(&self) -> Result<PlayMode, SpeakerError> {
+        let transport_settings = self.get_transport_settings().await?;
+
+        Ok(transport_settings.play_mode)
+    }
+
+    /// Changes the shuffle and repeat mode of the speaker
+    ///
+    /// * `mode` - the play mode to set the speaker to
+    pub async fn set_play_mode(&self, mode: PlayMode) -> Result<(), SpeakerError> {
+        let action_name = "SetPlayMode";
+        let service = Service::AVTransport;
+
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0"), ("NewPlayMode", mode.as_str())];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Returns whether the speaker crossfades between tracks in the queue
+    pub async fn get_crossfade_mode(&self) -> Result<bool, SpeakerError> {
+        let action_name = "GetCrossfadeMode";
+        let service = Service::AVTransport;
+
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0")];
+
+        let xml_response = self.make_request(service, action_name, arguments).await?;
+
+        parse_crossfade_mode_xml(xml_response).map_err(SpeakerError::from)
+    }
+
+    /// Makes the speaker crossfade between tracks in the queue
+    pub async fn enable_crossfade(&self) -> Result<(), SpeakerError> {
+        self.set_crossfade_mode(true).await
+    }
+
+    /// Makes the speaker stop crossfading between tracks in the queue
+    pub async fn disable_crossfade(&self) -> Result<(), SpeakerError> {
+        self.set_crossfade_mode(false).await
+    }
+
+    async fn set_crossfade_mode(&self, enabled: bool) -> Result<(), SpeakerError> {
+        let action_name = "SetCrossfadeMode";
+        let service = Service::AVTransport;
+
+        let arguments: &[(&str, &str)] = &[
+            ("InstanceID", "0"),
+            ("CrossfadeMode", if enabled { "1" } else { "0" }),
+        ];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Starts playing from the specified position in the current track
+    ///
+    /// * `new_position` - the position to start playing from, as hh:mm:ss
+    pub async fn seek(&self, new_position: &str) -> Result<(), SpeakerError> {
+        self.seek_with_unit("REL_TIME", new_position).await
+    }
+
+    /// Starts playing from the specified position in the current track
+    ///
+    /// * `position` - the position to start playing from
+    pub async fn seek_duration(&self, position: Duration) -> Result<(), SpeakerError> {
+        self.seek(&duration_to_time_str(position)).await
+    }
+
+    /// Starts playing the track at the specified position in the queue
+    ///
+    /// * `track_number` - the position of the track in the queue, starting at 1
+    ///
+    /// Note: this function will error if you use it before you have entered the queue,
+    /// or with a track number that is not in the queue
+    pub async fn seek_to_track(&self, track_number: u32) -> Result<(), SpeakerError> {
+        self.seek_with_unit("TRACK_NR", &track_number.to_string())
+            .await
+    }
+
+    async fn seek_with_unit(&self, unit: &str, target: &str) -> Result<(), SpeakerError> {
+        let action_name = "Seek";
+        let service = Service::AVTransport;
+
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0"), ("Unit", unit), ("Target", target)];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Returns all tracks in the queue
+    pub async fn get_queue(&self) -> Result<Vec<QueueItem>, SpeakerError> {
+        get_all_pages(|start| self.get_queue_page(start, 100)).await
+    }
+
+    /// Returns a page of tracks in the queue, along with the total number of tracks in the queue
+    ///
+    /// * `offset` - the index of the first track to return, starting at 0
+    /// * `count` - the maximum number of tracks to return (the speaker may return fewer)
+    pub async fn get_queue_page(
+        &self,
+        offset: u32,
+        count: u32,
+    ) -> Result<(Vec<QueueItem>, u32), SpeakerError> {
+        let xml_response = self.browse_direct_children("Q:0", offset, count).await?;
+
+        parse_queue_xml(xml_response).map_err(SpeakerError::from)
+    }
+
+    /// Returns the number of tracks in the queue, without fetching the tracks themselves
+    pub async fn get_queue_length(&self) -> Result<u32, SpeakerError> {
+        let xml_response = self.browse_direct_children("Q:0", 0, 1).await?;
+
+        parse_total_matches_xml(xml_response).map_err(SpeakerError::from)
+    }
+
+    /// Saves the tracks in the queue as a Sonos playlist, returning the ID of the playlist, ex. `SQ:3`
+    ///
+    /// * `name` - the name of the playlist
+    ///
+    /// If a playlist with the same name already exists, the speaker updates that playlist rather than creating another
+    pub async fn save_queue_as_playlist(&self, name: &str) -> Result<String, SpeakerError> {
+        let action_name = "SaveQueue";
+        let service = Service::AVTransport;
+
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0"), ("Title", name), ("ObjectID", "")];
+
+        let xml_response = self.make_request(service, action_name, arguments).await?;
+
+        parse_save_queue_xml(xml_response).map_err(SpeakerError::from)
+    }
+
+    /// Start playback from the queue (you must enter the queue before playing tracks from it)
+    pub async fn enter_queue(&self) -> Result<(), SpeakerError> {
+        let queue_uri = format!("{}{}#0", QUEUE_URI_PREFIX, &self.uuid);
+        self.set_current_uri(&queue_uri).await?;
+
+        Ok(())
+    }
+
+    /// Returns a page of the items and containers in a container of the speaker's content
+    ///
+    /// * `object_id` - the ID of the container to browse, ex. `FV:2` for Sonos favorites, or the ID of a `ContentContainer`
+    /// * `start` - the index of the first node to return
+    /// * `count` - the maximum number of nodes to return (the speaker may return fewer, see `BrowseResult::total_matches`)
+    pub async fn browse(
+        &self,
+        object_id: &str,
+        start: u32,
+        count: u32,
+    ) -> Result<BrowseResult, SpeakerError> {
+        let xml_response = self.browse_direct_children(object_id, start, count).await?;
+
+        parse_browse_xml(xml_response).map_err(SpeakerError::from)
+    }
+
+    async fn browse_all(&self, object_id: &str) -> Result<Vec<ContentNode>, SpeakerError> {
+        get_all_pages(|start| async move {
+            let page = self.browse(object_id, start, 100).await?;
+
+            Ok((page.items, page.total_matches))
+        })
+        .await
+    }
+
+    async fn browse_direct_children(
+        &self,
+        object_id: &str,
+        start: u32,
+        count: u32,
+    ) -> Result<String, SpeakerError> {
+        let action_name = "Browse";
+        let service = Service::ContentDirectory;
+
+        let start = start.to_string();
+        let count = count.to_string();
+
+        let arguments: &[(&str, &str)] = &[
+            ("ObjectID", object_id),
+            ("BrowseFlag", "BrowseDirectChildren"),
+            ("Filter", "*"),
+            ("StartingIndex", &start),
+            ("RequestedCount", &count),
+            ("SortCriteria", ""),
+        ];
+
+        self.make_request(service, action_name, arguments).await
+    }
+
+    /// Returns the items saved in Sonos favorites
+    pub async fn get_favorites(&self) -> Result<Vec<FavoriteItem>, SpeakerError> {
+        Ok(self
+            .browse_all("FV:2")
+            .await?
+            .into_iter()
+            .filter_map(|node| match node {
+                ContentNode::Item(item) => Some(FavoriteItem {
+                    title: item.title,
+                    uri: item.uri,
+                    metadata: item.metadata,
+                    album_art_uri: item.art_uri,
+                }),
+                ContentNode::Container(_) => None,
+            })
+            .collect())
+    }
+
+    /// Starts playing an item from Sonos favorites
+    ///
+    /// * `item` - the favorite to play, as returned by `get_favorites`
+    pub async fn play_favorite(&self, item: &FavoriteItem) -> Result<(), SpeakerError> {
+        self.set_av_transport_uri(&item.uri, &item.metadata).await?;
+
+        self.play().await
+    }
+
+    /// Starts playing the first item from Sonos favorites whose name matches the given name (case-insensitive)
+    ///
+    /// * `name` - the name of the favorite, as shown in the Sonos app
+    pub async fn play_favorite_by_name(&self, name: &str) -> Result<(), SpeakerError> {
+        let lowercase_name = name.to_lowercase();
+
+        let favorite = self
+            .get_favorites()
+            .await?
+            .into_iter()
+            .find(|favorite| favorite.title.to_lowercase() == lowercase_name)
+            .ok_or_else(|| SpeakerError::InvalidInput(format!("no favorite named: {}", name)))?;
+
+        self.play_favorite(&favorite).await
+    }
+
+    /// Returns all Sonos playlists
+    pub async fn list_playlists(&self) -> Result<Vec<PlaylistItem>, SpeakerError> {
+        Ok(self
+            .browse_all("SQ:")
+            .await?
+            .into_iter()
+            .filter_map(|node| match node {
+                ContentNode::Container(container) => Some(PlaylistItem {
+                    id: container.id,
+                    title: container.title,
+                    uri: container.uri,
+                    track_count: container.child_count,
+                }),
+                ContentNode::Item(_) => None,
+            })
+            .collect())
+    }
+
+    /// Replaces the queue with the tracks in a Sonos playlist, then starts playing it
+    ///
+    /// * `item` - the playlist to play, as returned by `list_playlists`
+    pub async fn play_playlist(&self, item: &PlaylistItem) -> Result<(), SpeakerError> {
+        self.clear_queue().await?;
+        self.add_track_to_queue(&item.uri).await?;
+        self.enter_queue().await?;
+
+        self.play().await
+    }
+
+    /// Returns the radio stations saved in the speaker's household (under "My Radio Stations" in TuneIn)
+    pub async fn browse_radio_stations(&self) -> Result<Vec<RadioStation>, SpeakerError> {
+        Ok(self
+            .browse_all("R:0/0")
+            .await?
+            .into_iter()
+            .filter_map(|node| match node {
+                ContentNode::Item(item) => Some(RadioStation {
+                    title: item.title,
+                    uri: item.uri,
+                    logo_uri: item.art_uri,
+                }),
+                ContentNode::Container(_) => None,
+            })
+            .collect())
+    }
+
+    /// Starts playing a radio station
+    ///
+    /// * `station` - the station to play, as returned by `browse_radio_stations`
+    pub async fn play_radio_station(&self, station: &RadioStation) -> Result<(), SpeakerError> {
+        let metadata = DidlMetadata::for_stream(&station.title, &station.uri);
+
+        self.set_uri_with_metadata(&station.uri, &metadata).await?;
+
+        self.play().await
+    }
+
+    /// Starts playing audio from the speaker's line-in input
+    ///
+    /// Note: this is only supported by speakers with a line-in jack (such as the Play:5, Port, and Amp),
+    /// and will return a `SonosError` from the speaker otherwise
+    pub async fn play_line_in(&self) -> Result<(), SpeakerError> {
+        self.play_line_in_from(self).await
+    }
+
+    /// Starts playing audio from another speaker's line-in input
+    ///
+    /// * `source_speaker` - the speaker whose line-in input should be played
+    ///
+    /// Note: this will return a `SonosError` from the speaker if the source speaker does not have a line-in jack
+    pub async fn play_line_in_from(&self, source_speaker: &Speaker) -> Result<(), SpeakerError> {
+        let line_in_uri = format!("x-rincon-stream:{}", &source_speaker.uuid);
+        self.set_current_uri(&line_in_uri).await?;
+
+        self.play().await
+    }
+
+    /// Add a track to the end of the queue, returning where the track was added
+    ///
+    /// * `uri` - the URI of the track to add
+    pub async fn add_track_to_queue(&self, uri: &str) -> Result<AddToQueueResult, SpeakerError> {
+        self.add_uri_to_queue(uri, false).await
+    }
+
+    /// Add a track to the queue immediately after the current track, returning where the track was added
+    ///
+    /// * `uri` - the URI of the track to add
+    pub async fn add_track_next(&self, uri: &str) -> Result<AddToQueueResult, SpeakerError> {
+        self.add_uri_to_queue(uri, true).await
+    }
+
+    /// Adds several tracks to the end of the queue, returning where the tracks were added
+    ///
+    /// * `uris` - the URI of each track to add, with optional metadata describing it (if `None`, basic track metadata is used)
+    ///
+    /// Tracks are sent in batches of 16 per request, so adding many tracks is much faster than calling `add_track_to_queue` for each
+    pub async fn add_multiple_tracks_to_queue(
+        &self,
+        uris: &[(&str, Option<&DidlMetadata>)],
+    ) -> Result<AddToQueueResult, SpeakerError> {
+        // the URIs and metadata are each sent as space-separated lists
+        if let Some((uri, _)) = uris.iter().find(|(uri, _)| uri.contains(' ')) {
+            return Err(SpeakerError::InvalidInput(format!(
+                "URIs must not contain spaces: {}",
+                uri
+            )));
+        }
+
+        let mut batches = uris.chunks(MAX_URIS_PER_REQUEST);
+
+        let Some(first_batch) = batches.next() else {
+            return Err(SpeakerError::InvalidInput(String::from(
+                "no tracks to add to the queue",
+            )));
+        };
+
+        let mut result = self.add_batch_to_queue(first_batch).await?;
+
+        for batch in batches {
+            let batch_result = self.add_batch_to_queue(batch).await?;
+
+            result.num_tracks_added += batch_result.num_tracks_added;
+            result.new_queue_length = batch_result.new_queue_length;
+        }
+
+        Ok(result)
+    }
+
+    async fn add_batch_to_queue(
+        &self,
+        batch: &[(&str, Option<&DidlMetadata>)],
+    ) -> Result<AddToQueueResult, SpeakerError> {
+        let action_name = "AddMultipleURIsToQueue";
+        let service = Service::AVTransport;
+
+        let enqueued_uris = batch
+            .iter()
+            .map(|(uri, _)| *uri)
+            .collect::<Vec<&str>>()
+            .join(" ");
+
+        let enqueued_metadata = batch
+            .iter()
+            .map(|(uri, metadata)| match metadata {
+                Some(metadata) => metadata.to_xml_string(),
+                None => DidlMetadata::for_track(uri).to_xml_string(),
+            })
+            .collect::<Result<Vec<String>, XMLError>>()?
+            .join(" ");
+
+        let number_of_uris = batch.len().to_string();
+
+        let arguments: &[(&str, &str)] = &[
+            ("InstanceID", "0"),
+            ("UpdateID", "0"),
+            ("NumberOfURIs", &number_of_uris),
+            ("EnqueuedURIs", &enqueued_uris),
+            ("EnqueuedURIsMetaData", &enqueued_metadata),
+            ("ContainerURI", ""),
+            ("ContainerMetaData", ""),
+            ("DesiredFirstTrackNumberEnqueued", "0"),
+            ("EnqueueAsNext", "0"),
+        ];
+
+        let xml_response = self.make_request(service, action_name, arguments).await?;
+
+        parse_add_to_queue_xml(xml_response).map_err(SpeakerError::from)
+    }
+
+    async fn add_uri_to_queue(
+        &self,
+        uri: &str,
+        enqueue_as_next: bool,
+    ) -> Result<AddToQueueResult, SpeakerError> {
+        let action_name = "AddURIToQueue";
+        let service = Service::AVTransport;
+
+        let arguments: &[(&str, &str)] = &[
+            ("InstanceID", "0"),
+            ("EnqueuedURI", uri),
+            ("EnqueuedURIMetaData", ""),
+            ("DesiredFirstTrackNumberEnqueued", "0"),
+            ("EnqueueAsNext", if enqueue_as_next { "1" } else { "0" }),
+        ];
+
+        let xml_response = self.make_request(service, action_name, arguments).await?;
+
+        parse_add_to_queue_xml(xml_response).map_err(SpeakerError::from)
+    }
+
+    /// Removes the track at a position in the queue
+    ///
+    /// * `index` - the position of the track in the queue, starting at 1
+    ///
+    /// Returns `SonosError::InvalidSeekTarget` if there is no track at that position
+    pub async fn remove_track_from_queue(&self, index: u32) -> Result<(), SpeakerError> {
+        if index == 0 || index > self.get_queue_length().await? {
+            return Err(SpeakerError::from(SonosError::InvalidSeekTarget));
+        }
+
+        let action_name = "RemoveTrackFromQueue";
+        let service = Service::AVTransport;
+
+        let object_id = format!("Q:0/{}", index);
+
+        let arguments: &[(&str, &str)] = &[
+            ("InstanceID", "0"),
+            ("ObjectID", &object_id),
+            ("UpdateID", "0"),
+        ];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Removes a range of consecutive tracks from the queue
+    ///
+    /// * `start_index` - the position of the first track to remove, starting at 1
+    /// * `count` - the number of tracks to remove, at least 1
+    ///
+    /// This sends a single request, so it is faster than calling `remove_track_from_queue` for each track
+    pub async fn remove_track_range_from_queue(
+        &self,
+        start_index: u32,
+        count: u32,
+    ) -> Result<(), SpeakerError> {
+        if start_index == 0 {
+            return Err(SpeakerError::InvalidInput(String::from(
+                "queue positions start at 1",
+            )));
+        }
+
+        if count == 0 {
+            return Err(SpeakerError::InvalidInput(String::from(
+                "at least one track must be removed",
+            )));
+        }
+
+        let action_name = "RemoveTrackRangeFromQueue";
+        let service = Service::AVTransport;
+
+        let start_index = start_index.to_string();
+        let count = count.to_string();
+
+        let arguments: &[(&str, &str)] = &[
+            ("InstanceID", "0"),
+            // an update ID of 0 lets the speaker apply the change regardless of other changes to the queue
+            ("UpdateID", "0"),
+            ("StartingIndex", &start_index),
+            ("NumberOfTracks", &count),
+        ];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Moves a track to a different position in the queue
+    ///
+    /// * `from_index` - the current position of the track, starting at 1
+    /// * `to_index` - the position the track should end up at, starting at 1
+    ///
+    /// If the queue changes between reading its state and moving the track, the speaker rejects the move rather than moving the wrong track.
+    /// Returns `SonosError::InvalidSeekTarget` if either position is outside the queue
+    pub async fn move_track_in_queue(
+        &self,
+        from_index: u32,
+        to_index: u32,
+    ) -> Result<(), SpeakerError> {
+        let xml_response = self.browse_direct_children("Q:0", 0, 1).await?;
+        let (queue_length, update_id) = parse_browse_update_xml(xml_response)?;
+
+        let in_queue = |index: u32| (1..=queue_length).contains(&index);
+        if !in_queue(from_index) || !in_queue(to_index) {
+            return Err(SpeakerError::from(SonosError::InvalidSeekTarget));
+        }
+
+        if from_index == to_index {
+            return Ok(());
+        }
+
+        let action_name = "ReorderTracksInQueue";
+        let service = Service::AVTransport;
+
+        // the track is inserted before the track currently at this position, so moving later needs the position after the target
+        let insert_before = if to_index > from_index {
+            to_index + 1
+        } else {
+            to_index
+        };
+
+        let from_index = from_index.to_string();
+        let insert_before = insert_before.to_string();
+
+        let arguments: &[(&str, &str)] = &[
+            ("InstanceID", "0"),
+            ("StartingIndex", &from_index),
+            ("NumberOfTracks", "1"),
+            ("InsertBefore", &insert_before),
+            ("UpdateID", &update_id),
+        ];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Skips to the next track in the queue, erroring if there are no tracks after the current one
+    /// Note: this function will error if you use it before you have entered the queue
+    pub async fn move_to_next_track(&self) -> Result<(), SpeakerError> {
+        let action_name = "Next";
+        let service = Service::AVTransport;
+
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0")];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Goes back to the previous track in the queue, erroring if there are no tracks before the current one
+    /// Note: this function will error if you use it before you have entered the queue
+    pub async fn move_to_previous_track(&self) -> Result<(), SpeakerError> {
+        let action_name = "Previous";
+        let service = Service::AVTransport;
+
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0")];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Clears all tracks from the queue
+    pub async fn clear_queue(&self) -> Result<(), SpeakerError> {
+        let action_name = "RemoveAllTracksFromQueue";
+        let service = Service::AVTransport;
+
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0")];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Cuts off connections from any third party services trying to use the speaker
+    /// (Use this to stop playback from Spotify, for example)
+    pub async fn end_external_control(&self) -> Result<(), SpeakerError> {
+        let action_name = "EndDirectControlSession";
+        let service = Service::AVTransport;
+
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0")];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Returns whether the status light on the speaker is on
+    pub async fn get_led(&self) -> Result<bool, SpeakerError> {
+        let action_name = "GetLEDState";
+        let service = Service::DeviceProperties;
+
+        let arguments: &[(&str, &str)] = &[];
+
+        let xml_response = self.make_request(service, action_name, arguments).await?;
+
+        parse_getledstate_xml(xml_response).map_err(SpeakerError::from)
+    }
+
+    /// Turns the status light on the speaker on or off
+    ///
+    /// * `on` - whether the status light should be on
+    pub async fn set_led(&self, on: bool) -> Result<(), SpeakerError> {
+        let action_name = "SetLEDState";
+        let service = Service::DeviceProperties;
+
+        let arguments: &[(&str, &str)] = &[("DesiredLEDState", if on { "On" } else { "Off" })];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Returns whether the physical buttons on the speaker are locked
+    pub async fn get_button_lock(&self) -> Result<bool, SpeakerError> {
+        let action_name = "GetButtonLockState";
+        let service = Service::DeviceProperties;
+
+        let arguments: &[(&str, &str)] = &[];
+
+        let xml_response = self.make_request(service, action_name, arguments).await?;
+
+        parse_getbuttonlockstate_xml(xml_response).map_err(SpeakerError::from)
+    }
+
+    /// Locks or unlocks the physical buttons (play/pause, volume, etc...) on the speaker
+    ///
+    /// * `locked` - whether the buttons should be locked, so that pressing them does nothing
+    pub async fn set_button_lock(&self, locked: bool) -> Result<(), SpeakerError> {
+        let action_name = "SetButtonLockState";
+        let service = Service::DeviceProperties;
+
+        let arguments: &[(&str, &str)] =
+            &[("DesiredButtonLockState", if locked { "On" } else { "Off" })];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Changes the name of the room containing the speaker, keeping its icon and configuration
+    ///
+    /// * `new_name` - the new name of the room, ex. `Kitchen`
+    pub async fn rename_room(&mut self, new_name: &str) -> Result<(), SpeakerError> {
+        if new_name.trim().is_empty() {
+            return Err(SpeakerError::InvalidInput(
+                "room name must not be empty".to_string(),
+            ));
+        }
+
+        let (icon, configuration) = self.get_zone_attributes().await?;
+
+        let action_name = "SetZoneAttributes";
+        let service = Service::DeviceProperties;
+
+        let arguments: &[(&str, &str)] = &[
+            ("DesiredZoneName", new_name),
+            ("DesiredIcon", &icon),
+            ("DesiredConfiguration", &configuration),
+        ];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        self.room_name = new_name.to_owned();
+
+        Ok(())
+    }
+
+    /// Returns the icon and configuration of the zone (room) containing the speaker
+    async fn get_zone_attributes(&self) -> Result<(String, String), SpeakerError> {
+        let action_name = "GetZoneAttributes";
+        let service = Service::DeviceProperties;
+
+        let arguments: &[(&str, &str)] = &[];
+
+        let xml_response = self.make_request(service, action_name, arguments).await?;
+
+        parse_zone_attributes_xml(xml_response).map_err(SpeakerError::from)
+    }
+
+    /// Reboots the speaker
+    ///
+    /// The speaker closes its connections as it restarts, so a failure to read the response is not treated as an error.
+    /// The speaker is typically unreachable for about a minute afterwards, and leaves any group it was in
+    pub async fn reboot(&self) -> Result<(), SpeakerError> {
+        let action_name = "Reboot";
+        let service = Service::DeviceProperties;
+
+        let arguments: &[(&str, &str)] = &[];
+
+        match self.make_request(service, action_name, arguments).await {
+            Ok(_) => Ok(()),
+            // the request was sent, but the connection closed before the response could be read
+            Err(SpeakerError::ResponseError(error)) if error.is_body() || error.is_decode() => {
+                Ok(())
+            }
+            Err(SpeakerError::RequestError(error)) if is_connection_closed(&error) => Ok(()),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Returns all alarms configured in the speaker's household
+    ///
+    /// Alarms are shared between all speakers in a household, so any speaker can be used to list them
+    pub async fn list_alarms(&self) -> Result<Vec<Alarm>, SpeakerError> {
+        let action_name = "ListAlarms";
+        let service = Service::AlarmClock;
+
+        let arguments: &[(&str, &str)] = &[];
+
+        let xml_response = self.make_request(service, action_name, arguments).await?;
+
+        parse_list_alarms_xml(xml_response).map_err(SpeakerError::from)
+    }
+
+    /// Creates an alarm in the speaker's household, returning the ID of the new alarm
+    ///
+    /// * `alarm` - the settings for the alarm, including which speaker plays it
+    pub async fn create_alarm(&self, alarm: &AlarmConfig) -> Result<u32, SpeakerError> {
+        if alarm.start_time >= Duration::from_secs(24 * 3600) {
+            return Err(SpeakerError::InvalidInput(format!(
+                "alarm start time must be within a day: {:?}",
+                alarm.start_time
+            )));
+        }
+
+        if alarm.volume > 100 {
+            return Err(SpeakerError::InvalidInput(format!(
+                "invalid volume: {}",
+                alarm.volume
+            )));
+        }
+
+        let action_name = "CreateAlarm";
+        let service = Service::AlarmClock;
+
+        let start_time = duration_to_time_str(alarm.start_time);
+        let duration = duration_to_time_str(alarm.duration);
+        let recurrence = alarm.recurrence.to_recurrence_string();
+        let volume = alarm.volume.to_string();
+
+        let arguments: &[(&str, &str)] = &[
+            ("StartLocalTime", &start_time),
+            ("Duration", &duration),
+            ("Recurrence", &recurrence),
+            ("Enabled", if alarm.enabled { "1" } else { "0" }),
+            ("RoomUUID", &alarm.room_uuid),
+            ("ProgramURI", &alarm.program_uri),
+            ("ProgramMetaData", &alarm.program_metadata),
+            ("PlayMode", alarm.play_mode.as_str()),
+            ("Volume", &volume),
+            (
+                "IncludeLinkedZones",
+                if alarm.include_linked_zones { "1" } else { "0" },
+            ),
+        ];
+
+        let xml_response = self.make_request(service, action_name, arguments).await?;
+
+        parse_create_alarm_xml(xml_response).map_err(SpeakerError::from)
+    }
+
+    /// Deletes an alarm from the speaker's household
+    ///
+    /// * `alarm_id` - the ID of the alarm, as returned by `create_alarm` or `list_alarms`
+    pub async fn delete_alarm(&self, alarm_id: u32) -> Result<(), SpeakerError> {
+        let action_name = "DestroyAlarm";
+        let service = Service::AlarmClock;
+
+        let alarm_id = alarm_id.to_string();
+
+        let arguments: &[(&str, &str)] = &[("ID", &alarm_id)];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Enables an alarm, so that it plays at its next start time
+    ///
+    /// * `alarm_id` - the ID of the alarm, as returned by `create_alarm` or `list_alarms`
+    pub async fn enable_alarm(&self, alarm_id: u32) -> Result<(), SpeakerError> {
+        self.set_alarm_enabled(alarm_id, true).await
+    }
+
+    /// Disables an alarm, so that it does not play until enabled again
+    ///
+    /// * `alarm_id` - the ID of the alarm, as returned by `create_alarm` or `list_alarms`
+    pub async fn disable_alarm(&self, alarm_id: u32) -> Result<(), SpeakerError> {
+        self.set_alarm_enabled(alarm_id, false).await
+    }
+
+    async fn set_alarm_enabled(&self, alarm_id: u32, enabled: bool) -> Result<(), SpeakerError> {
+        // updating an alarm replaces all of its settings, so the current settings are sent back unchanged
+        let alarm = self
+            .list_alarms()
+            .await?
+            .into_iter()
+            .find(|alarm| alarm.id == alarm_id)
+            .ok_or_else(|| SpeakerError::InvalidInput(format!("no alarm with ID: {}", alarm_id)))?;
+
+        let action_name = "UpdateAlarm";
+        let service = Service::AlarmClock;
+
+        let alarm_id = alarm_id.to_string();
+        let recurrence = alarm.recurrence.to_recurrence_string();
+        let volume = alarm.volume.to_string();
+
+        let arguments: &[(&str, &str)] = &[
+            ("ID", &alarm_id),
+            ("StartLocalTime", &alarm.start_time),
+            ("Duration", &alarm.duration),
+            ("Recurrence", &recurrence),
+            ("Enabled", if enabled { "1" } else { "0" }),
+            ("RoomUUID", &alarm.room_uuid),
+            ("ProgramURI", &alarm.program_uri),
+            ("ProgramMetaData", &alarm.program_metadata),
+            ("PlayMode", alarm.play_mode.as_str()),
+            ("Volume", &volume),
+            (
+                "IncludeLinkedZones",
+                if alarm.include_linked_zones { "1" } else { "0" },
+            ),
+        ];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Makes the speaker join the group led by another speaker, playing whatever that group plays
+    ///
+    /// * `coordinator` - the speaker leading the group to join
+    pub async fn join_group(&self, coordinator: &Speaker) -> Result<(), SpeakerError> {
+        self.join_group_by_uuid(&coordinator.uuid).await
+    }
+
+    /// Makes the speaker join the group led by the speaker with the given UUID, playing whatever that group plays
+    ///
+    /// * `coordinator_uuid` - the UUID of the speaker leading the group to join
+    pub async fn join_group_by_uuid(&self, coordinator_uuid: &str) -> Result<(), SpeakerError> {
+        let group_uri = format!("x-rincon:{}", coordinator_uuid);
+        self.set_current_uri(&group_uri).await?;
+
+        Ok(())
+    }
+
+    /// Makes the speaker leave its current group, so that it plays on its own
+    ///
+    /// This does nothing if the speaker is not in a group
+    pub async fn leave_group(&self) -> Result<(), SpeakerError> {
+        let action_name = "BecomeCoordinatorOfStandaloneGroup";
+        let service = Service::AVTransport;
+
+        let arguments: &[(&str, &str)] = &[("InstanceID", "0")];
+
+        let _ = self.make_request(service, action_name, arguments).await?;
+
+        Ok(())
+    }
+
+    /// Returns whether the speaker leads its group (speakers that are not in a group lead their own group)
+    ///
+    /// Playback should be controlled through the group coordinator, since other members play whatever it plays
+    pub async fn is_group_coordinator(&self) -> Result<bool, SpeakerError> {
+        let zone_groups = self.get_zone_topology().await?;
+
+        Ok(zone_groups
+            .iter()
+            .any(|group| group.coordinator_uuid == self.uuid))
+    }
+
+    /// Returns the UUID of the speaker leading this speaker's group (this speaker's own UUID if it is not in a group)
+    pub async fn get_group_coordinator(&self) -> Result<String, SpeakerError> {
+        let coordinator_uuid = self
+            .get_own_zone_group()
+            .await?
+            .map(|group| group.coordinator_uuid)
+            .unwrap_or_else(|| self.uuid.to_owned());
+
+        Ok(coordinator_uuid)
+    }
+
+    /// Returns information about all speakers in this speaker's group, including this speaker
+    ///
+    /// Note: the group topology does not include speaker models, so the friendly name of each member is its room name,
+    /// and the model name, versions, and household ID of each member are empty
+    pub async fn get_group_members(&self) -> Result<Vec<BasicSpeakerInfo>, SpeakerError> {
+        let members = self
+            .get_own_zone_group()
+            .await?
+            .map(|group| group.members)
+            .unwrap_or_default();
+
+        Ok(members
+            .into_iter()
+            .map(|member| BasicSpeakerInfo {
+                ip_addr: member.ip_addr,
+                friendly_name: member.room_name.to_owned(),
+                room_name: member.room_name,
+                uuid: member.uuid,
+                model_name: String::new(),
+                model_number: String::new(),
+                software_version: String::new(),
+                hardware_version: String::new(),
+                household_id: String::new(),
+            })
+            .collect())
+    }
+
+    /// Returns this speaker's group, with a speaker object for its coordinator and each of its other members
+    pub async fn as_group(&self) -> Result<SpeakerGroup, SpeakerError> {
+        let coordinator_uuid = self.get_group_coordinator().await?;
+
+        let mut coordinator = None;
+        let mut members = Vec::new();
+
+        for info in self.get_group_members().await? {
+            let speaker = Speaker::from_basic_info_with_client(info, self.client.clone());
+
+            if speaker.uuid == coordinator_uuid {
+                coordinator = Some(speaker);
+            } else {
+                members.push(speaker);
+            }
+        }
+
+        let coordinator = coordinator.ok_or_else(|| {
+            SpeakerError::from(XMLError::UnexpectedValue(format!(
+                "coordinator not in group: {}",
+                coordinator_uuid
+            )))
+        })?;
+
+        Ok(SpeakerGroup {
+            coordinator,
+            members,
+        })
+    }
+
+    /// Makes every speaker in this speaker's group play on its own, breaking up the group
+    ///
+    /// This must be called on the group's coordinator, and returns an `InvalidInput` error otherwise
+    pub async fn dissolve_group(&self) -> Result<(), SpeakerError> {
+        if !self.is_group_coordinator().await? {
+            return Err(SpeakerError::InvalidInput(format!(
+                "not a group coordinator: {}",
+                self.uuid
+            )));
+        }
+
+        for info in self.get_group_members().await? {
+            if info.uuid != self.uuid {
+                Speaker::from_basic_info_with_client(info, self.client.clone())
+                    .leave_group()
+                    .await?;
+            }
+        }
+
+        self.leave_group().await
+    }
+
+    async fn get_own_zone_group(&self) -> Result<Option<ZoneGroup>, SpeakerError> {
+        let zone_groups = self.get_zone_topology().await?;
+
+        Ok(zone_groups
+            .into_iter()
+            .find(|group| group.members.iter().any(|member| member.uuid == self.uuid)))
+    }
+
+    /// Returns every group of speakers in the speaker's household, along with each group's members
+    ///
+    /// Speakers that cannot be controlled on their own, such as subwoofers and surrounds, are not included
+    pub async fn get_zone_topology(&self) -> Result<Vec<ZoneGroup>, SpeakerError> {
+        let action_name = "GetZoneGroupState";
+        let service = Service::ZoneGroupTopology;
+
+        let arguments: &[(&str, &str)] = &[];
+
+        let xml_response = self.make_request(service, action_name, arguments).await?;
+
+        parse_zone_group_state_xml(xml_response).map_err(SpeakerError::from)
+    }
+
+    /// Subscribes to events from the speaker's AVTransport service, which describe changes to playback, such as the current track or playback state
+    ///
+    /// * `callback_url` - the URL the speaker should send `NOTIFY` requests to, ex. `http://192.168.1.2:3400/events`
+    /// * `timeout_secs` - how long the subscription should last, in seconds (the speaker may choose a different length)
+    pub async fn subscribe_to_av_transport_events(
+        &self,
+        callback_url: &str,
+        timeout_secs: u32,
+    ) -> Result<EventSubscription, SpeakerError> {
+        if timeout_secs == 0 {
+            return Err(SpeakerError::InvalidInput(String::from(
+                "subscription timeout must be greater than 0",
+            )));
+        }
+
+        let event_endpoint = Service::AVTransport.get_event_endpoint();
+
+        let callback = format!("<{}>", callback_url);
+        let requested_timeout = format!("Second-{}", timeout_secs);
+
+        let response = self
+            .send_event_request(
+                "SUBSCRIBE",
+                event_endpoint,
+                &[
+                    ("CALLBACK", callback.as_str()),
+                    ("NT", "upnp:event"),
+                    ("TIMEOUT", requested_timeout.as_str()),
+                ],
+            )
+            .await?;
+
+        let sid = response
+            .headers()
+            .get("SID")
+            .and_then(|sid| sid.to_str().ok())
+            .ok_or_else(|| XMLError::ElementNotFound(String::from("SID header")))?
+            .to_owned();
+
+        let timeout = response
+            .headers()
+            .get("TIMEOUT")
+            .and_then(|timeout| timeout.to_str().ok())
+            .and_then(parse_timeout_header)
+            .unwrap_or(Duration::from_secs(timeout_secs as u64));
+
+        Ok(EventSubscription {
+            sid,
+            timeout,
+            expires_at: Instant::now() + timeout,
+            event_endpoint,
+        })
+    }
+}
+
+// the speaker returns a limited number of results per request, so results are requested a page at a time,
+// with `get_page` returning the page starting at the given index along with the total number of results
+async fn get_all_pages<T, F, Fut>(mut get_page: F) -> Result<Vec<T>, SpeakerError>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: Future<Output = Result<(Vec<T>, u32), SpeakerError>>,
+{
+    let mut results = Vec::new();
+
+    loop {
+        let (page, total_matches) = get_page(results.len() as u32).await?;
+
+        if page.is_empty() {
+            break;
+        }
+
+        results.extend(page);
+
+        if results.len() >= total_matches as usize {
+            break;
+        }
+    }
+
+    Ok(results)
+}
+
+// whether the connection was closed by the speaker before it sent a complete response
+fn is_connection_closed(error: &reqwest::Error) -> bool {
+    let mut source = error.source();
+
+    while let Some(error) = source {
+        if let Some(error) = error.downcast_ref::<hyper::Error>() {
+            return error.is_incomplete_message() || error.is_closed();
+        }
+
+        source = error.source();
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        convert::Infallible,
+        net::Ipv4Addr,
+        sync::{Arc, Mutex},
+    };
+
+    use hyper::{server::conn::Http, service::service_fn, Body, Request, Response};
+    use tokio::{io::AsyncReadExt, net::TcpListener};
+
+    use super::*;
+
+    // URIs are kept as they are written in XML, escaped
+    const STREAM_URI: &str = "x-sonosapi-stream:s12345?sid=254&amp;flags=8224";
+    const CLIP_URI: &str = "http://192.168.1.2/chime.mp3";
+
+    // the state of a speaker playing a radio stream, which rejects the actions that only apply to the queue
+    struct MockSpeaker {
+        uri: String,
+        playing: bool,
+        muted: bool,
+        fail_clip_playback: bool,
+        clip_never_stops: bool,
+        clip_played_muted: Option<bool>,
+        actions: Vec<String>,
+    }
+
+    impl MockSpeaker {
+        fn respond(&mut self, action: &str, body: &str) -> (u16, String) {
+            self.actions.push(action.to_owned());
+
+            let on_clip = self.uri == CLIP_URI;
+
+            let response = match action {
+                "GetTransportInfo" => {
+                    // the clip finishes as soon as it is checked on, unless it never stops
+                    let state = if self.playing && (!on_clip || self.clip_never_stops) {
+                        "PLAYING"
+                    } else {
+                        "STOPPED"
+                    };
+                    format!(
+                        "<CurrentTransportState>{}</CurrentTransportState><CurrentTransportStatus>OK</CurrentTransportStatus>",
+                        state
+                    )
+                }
+                "GetMediaInfo" => format!(
+                    "<CurrentURI>{}</CurrentURI><CurrentURIMetaData></CurrentURIMetaData>",
+                    self.uri
+                ),
+                "GetPositionInfo" => format!(
+                    "<Track>1</Track><TrackDuration>{}</TrackDuration><TrackMetaData>&lt;DIDL-Lite&gt;&lt;item&gt;&lt;dc:title&gt;Radio&lt;/dc:title&gt;&lt;/item&gt;&lt;/DIDL-Lite&gt;</TrackMetaData><TrackURI>{}</TrackURI><RelTime>0:05:23</RelTime>",
+                    if on_clip { "0:00:01" } else { "0:00:00" },
+                    self.uri
+                ),
+                "GetVolume" => String::from("<CurrentVolume>20</CurrentVolume>"),
+                "GetMute" => format!("<CurrentMute>{}</CurrentMute>", u8::from(self.muted)),
+                "SetMute" => {
+                    self.muted = body.contains("<DesiredMute>1</DesiredMute>");
+                    String::new()
+                }
+                "GetTransportSettings" => String::from(
+                    "<PlayMode>NORMAL</PlayMode><RecQualityMode>NOT_IMPLEMENTED</RecQualityMode>",
+                ),
+                "SetAVTransportURI" => {
+                    let start = body.find("<CurrentURI>").unwrap() + "<CurrentURI>".len();
+                    let end = body.find("</CurrentURI>").unwrap();
+                    self.uri = body[start..end].to_owned();
+                    self.playing = false;
+                    String::new()
+                }
+                "Play" if on_clip && self.fail_clip_playback => return upnp_error("701"),
+                "Play" => {
+                    if on_clip {
+                        self.clip_played_muted = Some(self.muted);
+                    }
+                    self.playing = true;
+                    String::new()
+                }
+                "SetPlayMode" | "Seek" if !self.uri.starts_with(QUEUE_URI_PREFIX) => {
+                    return upnp_error("712")
+                }
+                _ => String::new(),
+            };
+
+            (
+                200,
+                format!(
+                    "<Envelope><Body><{0}Response>{1}</{0}Response></Body></Envelope>",
+                    action, response
+                ),
+            )
+        }
+    }
+
+    fn upnp_error(code: &str) -> (u16, String) {
+        (
+            500,
+            format!("<Envelope><Body><Fault><detail><UPnPError><errorCode>{}</errorCode></UPnPError></detail></Fault></Body></Envelope>", code),
+        )
+    }
+
+    async fn handle_request(
+        request: Request<Body>,
+        mock: Arc<Mutex<MockSpeaker>>,
+    ) -> Result<Response<Body>, Infallible> {
+        let action = request
+            .headers()
+            .get("SOAPACTION")
+            .and_then(|action| action.to_str().ok())
+            .and_then(|action| action.split_once('#'))
+            .map(|(_, action)| action.to_owned())
+            .unwrap_or_default();
+
+        let body = hyper::body::to_bytes(request.into_body()).await.unwrap();
+
+        let (status, body) = mock
+            .lock()
+            .unwrap()
+            .respond(&action, &String::from_utf8_lossy(&body));
+
+        let mut response = Response::new(Body::from(body));
+        *response.status_mut() = StatusCode::from_u16(status).unwrap();
+        Ok(response)
+    }
+
+    // speakers are always reached on port 1400, so each test serves a mock speaker on its own loopback address
+    async fn serve_mock_speaker(ip_addr: Ipv4Addr, mock: Arc<Mutex<MockSpeaker>>) -> Speaker {
+        let listener = TcpListener::bind((ip_addr, 1400)).await.unwrap();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let mock = mock.clone();
+                tokio::spawn(async move {
+                    let service = service_fn(move |request| handle_request(request, mock.clone()));
+                    let _ = Http::new().serve_connection(stream, service).await;
+                });
+            }
+        });
+
+        mock_speaker_at(ip_addr)
+    }
+
+    fn mock_speaker_at(ip_addr: Ipv4Addr) -> Speaker {
+        Speaker::from_basic_info(BasicSpeakerInfo {
+            ip_addr,
+            friendly_name: String::from("Mock"),
+            room_name: String::from("Mock"),
+            uuid: String::from("RINCON_000E58A0123401400"),
+            model_name: String::new(),
+            model_number: String::new(),
+            software_version: String::new(),
+            hardware_version: String::new(),
+            household_id: String::new(),
+        })
+    }
+
+    #[test]
+    fn reboot_ignores_closed_connection() {
+        tokio_test::block_on(async {
+            let ip_addr = Ipv4Addr::new(127, 0, 78, 3);
+            let listener = TcpListener::bind((ip_addr, 1400)).await.unwrap();
+
+            // the speaker reads the request, then restarts without replying
+            tokio::spawn(async move {
+                let (mut stream, _) = listener.accept().await.unwrap();
+
+                let mut request = Vec::new();
+                let mut buf = [0; 1024];
+                while !String::from_utf8_lossy(&request).contains("</s:Envelope>") {
+                    let len = stream.read(&mut buf).await.unwrap();
+                    request.extend_from_slice(&buf[..len]);
+                }
+            });
+
+            mock_speaker_at(ip_addr).reboot().await.unwrap();
+        })
+    }
+
+    fn playing_stream() -> MockSpeaker {
+        MockSpeaker {
+            uri: STREAM_URI.to_owned(),
+            playing: true,
+            muted: false,
+            fail_clip_playback: false,
+            clip_never_stops: false,
+            clip_played_muted: None,
+            actions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn play_notification_resumes_stream() {
+        tokio_test::block_on(async {
+            let mock = Arc::new(Mutex::new(playing_stream()));
+            let speaker = serve_mock_speaker(Ipv4Addr::new(127, 0, 78, 1), mock.clone()).await;
+
+            speaker.play_notification(CLIP_URI, Some(40)).await.unwrap();
+
+            let mock = mock.lock().unwrap();
+            assert_eq!(mock.uri, STREAM_URI);
+            assert!(mock.playing);
+            assert!(!mock.actions.iter().any(|action| action == "Seek"));
+            assert!(!mock.actions.iter().any(|action| action == "SetPlayMode"));
+        })
+    }
+
+    #[test]
+    fn play_notification_restores_after_clip_error() {
+        tokio_test::block_on(async {
+            let mock = Arc::new(Mutex::new(MockSpeaker {
+                fail_clip_playback: true,
+                ..playing_stream()
+            }));
+            let speaker = serve_mock_speaker(Ipv4Addr::new(127, 0, 78, 2), mock.clone()).await;
+
+            let result = speaker.play_notification(CLIP_URI, None).await;
+
+            assert!(matches!(
+                result,
+                Err(SpeakerError::SonosError(SonosError::TransitionUnavailable))
+            ));
+
+            let mock = mock.lock().unwrap();
+            assert_eq!(mock.uri, STREAM_URI);
+            assert!(mock.playing);
+        })
+    }
+
+    #[test]
+    fn play_notification_unmutes_for_clip() {
+        tokio_test::block_on(async {
+            let mock = Arc::new(Mutex::new(MockSpeaker {
+                muted: true,
+                ..playing_stream()
+            }));
+            let speaker = serve_mock_speaker(Ipv4Addr::new(127, 0, 78, 4), mock.clone()).await;
+
+            speaker.play_notification(CLIP_URI, None).await.unwrap();
+
+            let mock = mock.lock().unwrap();
+            assert_eq!(mock.clip_played_muted, Some(false));
+            assert!(mock.muted);
+        })
+    }
+
+    #[test]
+    fn play_notification_stops_waiting_after_clip_length() {
+        tokio_test::block_on(async {
+            let mock = Arc::new(Mutex::new(MockSpeaker {
+                clip_never_stops: true,
+                ..playing_stream()
+            }));
+            let speaker = serve_mock_speaker(Ipv4Addr::new(127, 0, 78, 5), mock.clone()).await;
+
+            let started = Instant::now();
+            speaker.play_notification(CLIP_URI, None).await.unwrap();
+
+            // the clip reports a length of 1 second
+            assert!(started.elapsed() < Duration::from_secs(1) + NOTIFICATION_END_MARGIN * 2);
+
+            let mock = mock.lock().unwrap();
+            assert_eq!(mock.uri, STREAM_URI);
+            assert!(mock.playing);
+        })
+    }
 }
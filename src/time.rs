@@ -0,0 +1,141 @@
+//! Resources for converting between durations and the hh:mm:ss strings used by speakers
+
+use std::time::Duration;
+
+use crate::errors::XMLError;
+
+/// Parses a time string from a speaker, such as a track position or duration, into a `Duration`
+/// * `time_str` - the time, as h:mm:ss or hh:mm:ss (ex. `0:01:30` or `00:01:30`), optionally with fractional seconds
+///
+/// Sources without a position or duration, such as radio streams, report them as `NOT_IMPLEMENTED`, which is parsed as `Duration::ZERO`
+///
+/// ```rust
+/// # use rusty_sonos::time::parse_time_str;
+/// # use std::time::Duration;
+/// assert_eq!(parse_time_str("0:01:30").unwrap(), Duration::from_secs(90));
+/// assert_eq!(parse_time_str("NOT_IMPLEMENTED").unwrap(), Duration::ZERO);
+/// ```
+pub fn parse_time_str(time_str: &str) -> Result<Duration, XMLError> {
+    if time_str == "NOT_IMPLEMENTED" {
+        return Ok(Duration::ZERO);
+    }
+
+    let invalid_time = || XMLError::UnexpectedValue(format!("invalid time: {}", time_str));
+
+    let mut parts = time_str.split(':');
+
+    let (Some(hours), Some(minutes), Some(seconds), None) =
+        (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err(invalid_time());
+    };
+
+    let hours = hours.parse::<u64>().map_err(|_| invalid_time())?;
+    let minutes = minutes.parse::<u64>().map_err(|_| invalid_time())?;
+    let seconds = seconds.parse::<f64>().map_err(|_| invalid_time())?;
+
+    if minutes >= 60 || !(0.0..60.0).contains(&seconds) {
+        return Err(invalid_time());
+    }
+
+    Ok(Duration::from_secs(hours * 3600 + minutes * 60) + Duration::from_secs_f64(seconds))
+}
+
+/// Formats a duration as hh:mm:ss, ignoring any fractional seconds
+pub(crate) fn duration_to_time_str(duration: Duration) -> String {
+    let total_seconds = duration.as_secs();
+
+    let hours = total_seconds / 3600;
+    let minutes = (total_seconds % 3600) / 60;
+    let seconds = total_seconds % 60;
+
+    format!("{:02}:{:02}:{:02}", hours, minutes, seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_duration() {
+        assert_eq!(duration_to_time_str(Duration::from_secs(90)), "00:01:30");
+    }
+
+    #[test]
+    fn formats_duration_over_an_hour() {
+        assert_eq!(duration_to_time_str(Duration::from_secs(37230)), "10:20:30");
+    }
+
+    #[test]
+    fn formats_duration_without_fractional_seconds() {
+        assert_eq!(
+            duration_to_time_str(Duration::from_millis(90_999)),
+            "00:01:30"
+        );
+    }
+
+    #[test]
+    fn parses_single_digit_hours() {
+        assert_eq!(
+            parse_time_str("1:02:03").unwrap(),
+            Duration::from_secs(3723)
+        );
+    }
+
+    #[test]
+    fn parses_two_digit_hours() {
+        assert_eq!(
+            parse_time_str("01:02:03").unwrap(),
+            Duration::from_secs(3723)
+        );
+        assert_eq!(
+            parse_time_str("12:00:00").unwrap(),
+            Duration::from_secs(43200)
+        );
+    }
+
+    #[test]
+    fn parses_fractional_seconds() {
+        assert_eq!(
+            parse_time_str("0:00:01.500").unwrap(),
+            Duration::from_millis(1500)
+        );
+    }
+
+    #[test]
+    fn parses_not_implemented_as_zero() {
+        assert_eq!(parse_time_str("NOT_IMPLEMENTED").unwrap(), Duration::ZERO);
+    }
+
+    #[test]
+    fn rejects_minutes_or_seconds_out_of_range() {
+        assert!(parse_time_str("0:60:00").is_err());
+        assert!(parse_time_str("0:00:60").is_err());
+        assert!(parse_time_str("0:00:59.999").is_ok());
+    }
+
+    #[test]
+    fn rejects_wrong_number_of_fields() {
+        assert!(parse_time_str("01:30").is_err());
+        assert!(parse_time_str("0:00:01:30").is_err());
+        assert!(parse_time_str("").is_err());
+    }
+
+    #[test]
+    fn rejects_non_numeric_fields() {
+        assert!(parse_time_str("a:00:00").is_err());
+        assert!(parse_time_str("0:-1:00").is_err());
+    }
+
+    #[test]
+    fn round_trips() {
+        for seconds in [0, 59, 60, 3599, 3600, 86399, 360000] {
+            let duration = Duration::from_secs(seconds);
+
+            assert_eq!(
+                parse_time_str(&duration_to_time_str(duration)).unwrap(),
+                duration
+            );
+        }
+    }
+}
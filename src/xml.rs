@@ -1,8 +1,15 @@
-use std::{collections::HashMap, net::Ipv4Addr};
+use std::net::Ipv4Addr;
 
 use crate::{
+    alarm::AlarmRecurrence,
     errors::XMLError,
-    responses::{CurrentTrack, PlaybackState, PlaybackStatus, QueueItem},
+    events::{SpeakerEvent, TransportEvent},
+    metadata::{DidlMetadata, MUSIC_TRACK_CLASS},
+    responses::{
+        AddToQueueResult, Alarm, BrowseResult, ContentContainer, ContentItem, ContentNode,
+        CurrentTrack, PlayMode, PlaybackState, PlaybackStatus, QueueItem, TransportSettings,
+        ZoneGroup, ZoneMember,
+    },
     services::Service,
     speaker::BasicSpeakerInfo,
 };
@@ -55,6 +62,7 @@ fn clean_response_xml(xml: String) -> String {
         .replace("<u:", "<")
         .replace("</u:", "</")
         .replace("&quot;", "\"")
+        .replace("&apos;", "'")
         .replace("&lt;", "<")
         .replace("&gt;", ">")
         .replace(r#"xmlns:dc="http://purl.org/dc/elements/1.1/""#, "")
@@ -76,20 +84,31 @@ fn clean_response_xml(xml: String) -> String {
         .replace("</dc:", "</")
         .replace("</upnp:", "</")
         .replace("</r:", "</")
+        // embedded XML is escaped twice, so once its tags are unescaped above, its text still has escaped entities (ex. `&amp;amp;`).
+        // Only these are unescaped, since a lone `&amp;` belongs to the outer XML (ex. in a URI) and must stay escaped.
+        // `&amp;amp;` comes last so that `&amp;amp;lt;` is not unescaped twice
+        .replace("&amp;quot;", "&quot;")
+        .replace("&amp;apos;", "&apos;")
+        .replace("&amp;lt;", "&lt;")
+        .replace("&amp;gt;", "&gt;")
+        .replace("&amp;amp;", "&amp;")
 }
 
-pub(crate) fn parse_queue_xml(xml: String) -> Result<Vec<QueueItem>, XMLError> {
+/// Returns the tracks in a page of the queue, along with the total number of tracks in the queue
+pub(crate) fn parse_queue_xml(xml: String) -> Result<(Vec<QueueItem>, u32), XMLError> {
     let xml = clean_response_xml(xml);
 
     let parsed_xml = roxmltree::Document::parse(&xml).map_err(XMLError::from)?;
 
-    let items: Result<Vec<QueueItem>, XMLError> = parsed_xml
+    let total_matches = parse_browse_count(&parsed_xml, "TotalMatches")?;
+
+    let items = parsed_xml
         .descendants()
         .filter(|node| node.has_tag_name("item"))
-        .map(|item| parse_queue_item(item))
-        .collect();
+        .map(parse_queue_item)
+        .collect::<Result<Vec<QueueItem>, XMLError>>()?;
 
-    items
+    Ok((items, total_matches))
 }
 
 fn parse_queue_item(item: roxmltree::Node) -> Result<QueueItem, XMLError> {
@@ -103,6 +122,16 @@ fn parse_queue_item(item: roxmltree::Node) -> Result<QueueItem, XMLError> {
         .text()
         .map(str::to_string);
 
+    let album = get_tag_by_name_node(&item, "album")
+        .ok()
+        .and_then(|node| node.text())
+        .map(str::to_string);
+
+    let album_art_uri = get_tag_by_name_node(&item, "albumArtURI")
+        .ok()
+        .and_then(|node| node.text())
+        .map(str::to_string);
+
     let duration = res.attribute("duration").map(str::to_string);
 
     let uri = get_text(res)?.to_owned();
@@ -112,6 +141,146 @@ fn parse_queue_item(item: roxmltree::Node) -> Result<QueueItem, XMLError> {
         uri,
         title,
         artist,
+        album,
+        album_art_uri,
+    })
+}
+
+pub(crate) fn parse_browse_xml(xml: String) -> Result<BrowseResult, XMLError> {
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    let total_matches = parse_browse_count(&parsed_xml, "TotalMatches")?;
+
+    let number_returned = parse_browse_count(&parsed_xml, "NumberReturned")?;
+
+    let items = parse_escaped_xml(get_tag_by_name(&parsed_xml, "Result")?, |result| {
+        result
+            .root_element()
+            .children()
+            .filter_map(|node| {
+                if node.has_tag_name("item") {
+                    Some(parse_content_item(node).map(ContentNode::Item))
+                } else if node.has_tag_name("container") {
+                    Some(parse_content_container(node).map(ContentNode::Container))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    })?;
+
+    Ok(BrowseResult {
+        items,
+        total_matches,
+        number_returned,
+    })
+}
+
+pub(crate) fn parse_add_to_queue_xml(xml: String) -> Result<AddToQueueResult, XMLError> {
+    let xml = clean_response_xml(xml);
+
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    let parse_number = |tag_name: &str| -> Result<u32, XMLError> {
+        let number = get_text(get_tag_by_name(&parsed_xml, tag_name)?)?;
+
+        number
+            .parse::<u32>()
+            .map_err(|_| XMLError::UnexpectedValue(format!("invalid {}: {}", tag_name, number)))
+    };
+
+    Ok(AddToQueueResult {
+        first_track_number_enqueued: parse_number("FirstTrackNumberEnqueued")?,
+        num_tracks_added: parse_number("NumTracksAdded")?,
+        new_queue_length: parse_number("NewQueueLength")?,
+    })
+}
+
+pub(crate) fn parse_save_queue_xml(xml: String) -> Result<String, XMLError> {
+    let xml = clean_response_xml(xml);
+
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    get_text(get_tag_by_name(&parsed_xml, "AssignedObjectID")?)
+}
+
+/// Returns the total number of nodes in a browsed container, along with the ID of the container's latest update
+pub(crate) fn parse_browse_update_xml(xml: String) -> Result<(u32, String), XMLError> {
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    let total_matches = parse_browse_count(&parsed_xml, "TotalMatches")?;
+
+    let update_id = get_text(get_tag_by_name(&parsed_xml, "UpdateID")?)?;
+
+    Ok((total_matches, update_id))
+}
+
+pub(crate) fn parse_total_matches_xml(xml: String) -> Result<u32, XMLError> {
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    parse_browse_count(&parsed_xml, "TotalMatches")
+}
+
+fn parse_browse_count(parsed_xml: &Document, tag_name: &str) -> Result<u32, XMLError> {
+    let count = get_text(get_tag_by_name(parsed_xml, tag_name)?)?;
+
+    count
+        .parse::<u32>()
+        .map_err(|_| XMLError::UnexpectedValue(format!("invalid {}: {}", tag_name, count)))
+}
+
+fn parse_content_item(item: roxmltree::Node) -> Result<ContentItem, XMLError> {
+    let id = get_attribute(item, "id")?.to_owned();
+
+    let title = get_text(get_tag_by_name_node(&item, "title")?)?;
+
+    let uri = get_tag_by_name_node(&item, "res")
+        .ok()
+        .and_then(|node| node.text())
+        .unwrap_or_default()
+        .to_owned();
+
+    let metadata = get_tag_by_name_node(&item, "resMD")
+        .ok()
+        .and_then(|node| node.text())
+        .unwrap_or_default()
+        .to_owned();
+
+    let art_uri = get_tag_by_name_node(&item, "albumArtURI")
+        .ok()
+        .and_then(|node| node.text())
+        .map(str::to_string);
+
+    Ok(ContentItem {
+        id,
+        title,
+        uri,
+        metadata,
+        art_uri,
+    })
+}
+
+fn parse_content_container(container: roxmltree::Node) -> Result<ContentContainer, XMLError> {
+    let id = get_attribute(container, "id")?.to_owned();
+
+    let title = get_text(get_tag_by_name_node(&container, "title")?)?;
+
+    let uri = get_tag_by_name_node(&container, "res")
+        .ok()
+        .and_then(|node| node.text())
+        .unwrap_or_default()
+        .to_owned();
+
+    let child_count = container
+        .attribute("childCount")
+        .and_then(|count| count.parse::<u32>().ok())
+        .unwrap_or_default();
+
+    Ok(ContentContainer {
+        id,
+        title,
+        uri,
+        child_count,
     })
 }
 
@@ -133,14 +302,41 @@ pub(crate) fn parse_current_track_xml(xml: String) -> Result<CurrentTrack, XMLEr
         .and_then(|node| node.text())
         .map(str::to_string);
 
+    let album = get_tag_by_name(&parsed_xml, "album")
+        .ok()
+        .and_then(|node| node.text())
+        .map(str::to_string);
+
+    let album_art_uri = get_tag_by_name(&parsed_xml, "albumArtURI")
+        .ok()
+        .and_then(|node| node.text())
+        .map(str::to_string);
+
+    let genre = get_tag_by_name(&parsed_xml, "genre")
+        .ok()
+        .and_then(|node| node.text())
+        .map(str::to_string);
+
     let position = get_text(get_tag_by_name(&parsed_xml, "RelTime")?)?;
 
+    // an empty transport reports the track number as 0 or NOT_IMPLEMENTED, while streams usually report 1,
+    // so this only identifies a queue position when the transport is playing from the queue
+    let track_number = get_tag_by_name(&parsed_xml, "Track")
+        .ok()
+        .and_then(|node| node.text())
+        .and_then(|text| text.parse::<u32>().ok())
+        .filter(|track_number| *track_number != 0);
+
     Ok(CurrentTrack {
         position,
+        track_number,
         duration,
         uri,
         title,
         artist,
+        album,
+        album_art_uri,
+        genre,
     })
 }
 
@@ -156,6 +352,125 @@ pub(crate) fn parse_getvolume_xml(xml: String) -> Result<u8, XMLError> {
         .map_err(|_| XMLError::UnexpectedValue(format!("invalid volume: {}", volume)))
 }
 
+pub(crate) fn parse_setrelativevolume_xml(xml: String) -> Result<u8, XMLError> {
+    let xml = clean_response_xml(xml);
+
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    let volume = get_text(get_tag_by_name(&parsed_xml, "NewVolume")?)?;
+
+    volume
+        .parse::<u8>()
+        .map_err(|_| XMLError::UnexpectedValue(format!("invalid volume: {}", volume)))
+}
+
+pub(crate) fn parse_getmute_xml(xml: String) -> Result<bool, XMLError> {
+    let xml = clean_response_xml(xml);
+
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    let mute = get_text(get_tag_by_name(&parsed_xml, "CurrentMute")?)?;
+
+    parse_bool(&mute, "mute")
+}
+
+pub(crate) fn parse_getloudness_xml(xml: String) -> Result<bool, XMLError> {
+    let xml = clean_response_xml(xml);
+
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    let loudness = get_text(get_tag_by_name(&parsed_xml, "CurrentLoudness")?)?;
+
+    parse_bool(&loudness, "loudness")
+}
+
+pub(crate) fn parse_crossfade_mode_xml(xml: String) -> Result<bool, XMLError> {
+    let xml = clean_response_xml(xml);
+
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    let crossfade_mode = get_text(get_tag_by_name(&parsed_xml, "CrossfadeMode")?)?;
+
+    parse_bool(&crossfade_mode, "crossfade mode")
+}
+
+pub(crate) fn parse_getledstate_xml(xml: String) -> Result<bool, XMLError> {
+    let xml = clean_response_xml(xml);
+
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    let led_state = get_text(get_tag_by_name(&parsed_xml, "CurrentLEDState")?)?;
+
+    parse_on_off(&led_state, "LED state")
+}
+
+pub(crate) fn parse_getbuttonlockstate_xml(xml: String) -> Result<bool, XMLError> {
+    let xml = clean_response_xml(xml);
+
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    let button_lock_state = get_text(get_tag_by_name(&parsed_xml, "CurrentButtonLockState")?)?;
+
+    parse_on_off(&button_lock_state, "button lock state")
+}
+
+fn parse_on_off(value: &str, label: &str) -> Result<bool, XMLError> {
+    match value {
+        "On" => Ok(true),
+        "Off" => Ok(false),
+        _ => Err(XMLError::UnexpectedValue(format!(
+            "invalid {}: {}",
+            label, value
+        ))),
+    }
+}
+
+fn parse_bool(value: &str, label: &str) -> Result<bool, XMLError> {
+    match value {
+        "1" => Ok(true),
+        "0" => Ok(false),
+        _ => Err(XMLError::UnexpectedValue(format!(
+            "invalid {}: {}",
+            label, value
+        ))),
+    }
+}
+
+pub(crate) fn parse_getbass_xml(xml: String) -> Result<i8, XMLError> {
+    let xml = clean_response_xml(xml);
+
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    let bass = get_text(get_tag_by_name(&parsed_xml, "CurrentBass")?)?;
+
+    bass.parse::<i8>()
+        .map_err(|_| XMLError::UnexpectedValue(format!("invalid bass: {}", bass)))
+}
+
+pub(crate) fn parse_gettreble_xml(xml: String) -> Result<i8, XMLError> {
+    let xml = clean_response_xml(xml);
+
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    let treble = get_text(get_tag_by_name(&parsed_xml, "CurrentTreble")?)?;
+
+    treble
+        .parse::<i8>()
+        .map_err(|_| XMLError::UnexpectedValue(format!("invalid treble: {}", treble)))
+}
+
+pub(crate) fn parse_geteq_xml(xml: String) -> Result<i8, XMLError> {
+    let xml = clean_response_xml(xml);
+
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    let value = get_text(get_tag_by_name(&parsed_xml, "CurrentValue")?)?;
+
+    value
+        .parse::<i8>()
+        .map_err(|_| XMLError::UnexpectedValue(format!("invalid EQ value: {}", value)))
+}
+
 pub(crate) fn parse_playback_status_xml(xml: String) -> Result<PlaybackStatus, XMLError> {
     let xml = clean_response_xml(xml);
 
@@ -174,6 +489,336 @@ pub(crate) fn parse_playback_status_xml(xml: String) -> Result<PlaybackStatus, X
     })
 }
 
+pub(crate) fn parse_transport_settings_xml(xml: String) -> Result<TransportSettings, XMLError> {
+    let xml = clean_response_xml(xml);
+
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    let play_mode = get_text(get_tag_by_name(&parsed_xml, "PlayMode")?)?;
+    let play_mode = PlayMode::new(&play_mode)
+        .map_err(|_| XMLError::UnexpectedValue(format!("invalid play mode: {}", play_mode)))?;
+
+    let rec_quality_mode = get_text(get_tag_by_name(&parsed_xml, "RecQualityMode")?)?;
+
+    Ok(TransportSettings {
+        play_mode,
+        rec_quality_mode,
+    })
+}
+
+pub(crate) fn parse_media_info_xml(xml: String) -> Result<(String, String), XMLError> {
+    // the metadata is escaped DIDL-Lite XML, which must be kept as text rather than cleaned into elements
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    let uri = get_tag_by_name(&parsed_xml, "CurrentURI")?
+        .text()
+        .unwrap_or_default()
+        .to_owned();
+
+    let metadata = get_tag_by_name(&parsed_xml, "CurrentURIMetaData")?
+        .text()
+        .unwrap_or_default()
+        .to_owned();
+
+    Ok((uri, metadata))
+}
+
+pub(crate) fn parse_zone_attributes_xml(xml: String) -> Result<(String, String), XMLError> {
+    let xml = clean_response_xml(xml);
+
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    let icon = get_tag_by_name(&parsed_xml, "CurrentIcon")?
+        .text()
+        .unwrap_or_default()
+        .to_owned();
+
+    let configuration = get_tag_by_name(&parsed_xml, "CurrentConfiguration")?
+        .text()
+        .unwrap_or_default()
+        .to_owned();
+
+    Ok((icon, configuration))
+}
+
+pub(crate) fn parse_zone_group_state_xml(xml: String) -> Result<Vec<ZoneGroup>, XMLError> {
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    parse_escaped_xml(get_tag_by_name(&parsed_xml, "ZoneGroupState")?, |state| {
+        state
+            .descendants()
+            .filter(|node| node.has_tag_name("ZoneGroup"))
+            .map(parse_zone_group)
+            .collect()
+    })
+}
+
+fn parse_zone_group(group: roxmltree::Node) -> Result<ZoneGroup, XMLError> {
+    let coordinator_uuid = group
+        .attribute("Coordinator")
+        .ok_or(XMLError::ElementNotFound(String::from("Coordinator")))?
+        .to_owned();
+
+    // invisible members, such as subwoofers and surrounds, cannot be controlled on their own
+    let members = group
+        .descendants()
+        .filter(|node| node.has_tag_name("ZoneGroupMember"))
+        .filter(|node| node.attribute("Invisible") != Some("1"))
+        .map(|member| parse_zone_member(member, &coordinator_uuid))
+        .collect::<Result<Vec<ZoneMember>, XMLError>>()?;
+
+    Ok(ZoneGroup {
+        coordinator_uuid,
+        members,
+    })
+}
+
+fn parse_zone_member(
+    member: roxmltree::Node,
+    coordinator_uuid: &str,
+) -> Result<ZoneMember, XMLError> {
+    let uuid = member
+        .attribute("UUID")
+        .ok_or(XMLError::ElementNotFound(String::from("UUID")))?
+        .to_owned();
+
+    let room_name = member
+        .attribute("ZoneName")
+        .ok_or(XMLError::ElementNotFound(String::from("ZoneName")))?
+        .to_owned();
+
+    let location = member
+        .attribute("Location")
+        .ok_or(XMLError::ElementNotFound(String::from("Location")))?;
+
+    // the location is the speaker's description URL, ex. http://192.168.1.10:1400/xml/device_description.xml
+    let ip_addr = location
+        .trim_start_matches("http://")
+        .split([':', '/'])
+        .next()
+        .and_then(|host| host.parse::<Ipv4Addr>().ok())
+        .ok_or_else(|| XMLError::UnexpectedValue(format!("invalid location: {}", location)))?;
+
+    let is_coordinator = uuid == coordinator_uuid;
+
+    Ok(ZoneMember {
+        uuid,
+        room_name,
+        ip_addr,
+        is_coordinator,
+    })
+}
+
+pub(crate) fn parse_list_alarms_xml(xml: String) -> Result<Vec<Alarm>, XMLError> {
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    parse_escaped_xml(get_tag_by_name(&parsed_xml, "CurrentAlarmList")?, |list| {
+        list.descendants()
+            .filter(|node| node.has_tag_name("Alarm"))
+            .map(parse_alarm)
+            .collect()
+    })
+}
+
+fn parse_alarm(alarm: roxmltree::Node) -> Result<Alarm, XMLError> {
+    let id = get_attribute(alarm, "ID")?;
+    let id = id
+        .parse::<u32>()
+        .map_err(|_| XMLError::UnexpectedValue(format!("invalid alarm ID: {}", id)))?;
+
+    let start_time = get_attribute(alarm, "StartTime")?.to_owned();
+
+    let duration = get_attribute(alarm, "Duration")?.to_owned();
+
+    let recurrence = get_attribute(alarm, "Recurrence")?;
+    let recurrence = AlarmRecurrence::new(recurrence).map_err(|_| {
+        XMLError::UnexpectedValue(format!("invalid alarm recurrence: {}", recurrence))
+    })?;
+
+    let enabled = parse_bool(get_attribute(alarm, "Enabled")?, "alarm enabled")?;
+
+    let room_uuid = get_attribute(alarm, "RoomUUID")?.to_owned();
+
+    let program_uri = get_attribute(alarm, "ProgramURI")?.to_owned();
+
+    let program_metadata = alarm
+        .attribute("ProgramMetaData")
+        .unwrap_or_default()
+        .to_owned();
+
+    let play_mode = get_attribute(alarm, "PlayMode")?;
+    let play_mode = PlayMode::new(play_mode)
+        .map_err(|_| XMLError::UnexpectedValue(format!("invalid play mode: {}", play_mode)))?;
+
+    let volume = get_attribute(alarm, "Volume")?;
+    let volume = volume
+        .parse::<u8>()
+        .map_err(|_| XMLError::UnexpectedValue(format!("invalid alarm volume: {}", volume)))?;
+
+    let include_linked_zones = parse_bool(
+        get_attribute(alarm, "IncludeLinkedZones")?,
+        "alarm include linked zones",
+    )?;
+
+    Ok(Alarm {
+        id,
+        start_time,
+        duration,
+        recurrence,
+        enabled,
+        room_uuid,
+        program_uri,
+        program_metadata,
+        play_mode,
+        volume,
+        include_linked_zones,
+    })
+}
+
+pub(crate) fn parse_create_alarm_xml(xml: String) -> Result<u32, XMLError> {
+    let xml = clean_response_xml(xml);
+
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    let assigned_id = get_text(get_tag_by_name(&parsed_xml, "AssignedID")?)?;
+
+    assigned_id
+        .parse::<u32>()
+        .map_err(|_| XMLError::UnexpectedValue(format!("invalid alarm ID: {}", assigned_id)))
+}
+
+fn get_attribute<'a>(node: roxmltree::Node<'a, '_>, name: &str) -> Result<&'a str, XMLError> {
+    node.attribute(name)
+        .ok_or_else(|| XMLError::ElementNotFound(name.to_string()))
+}
+
+pub(crate) fn parse_event_xml(xml: String, sid: &str) -> Result<Vec<SpeakerEvent>, XMLError> {
+    let parsed_xml = roxmltree::Document::parse(&xml)?;
+
+    let mut events = Vec::new();
+
+    for last_change in parsed_xml
+        .descendants()
+        .filter(|node| node.has_tag_name("LastChange"))
+    {
+        parse_escaped_xml(last_change, |parsed_change| {
+            let value = |tag_name: &str| {
+                parsed_change
+                    .descendants()
+                    .find(|node| node.has_tag_name(tag_name))
+                    .and_then(|node| node.attribute("val"))
+            };
+
+            if let Some(state) = value("TransportState") {
+                let state = PlaybackState::new(state).map_err(|_| {
+                    XMLError::UnexpectedValue(format!("invalid playback state: {}", state))
+                })?;
+
+                events.push(SpeakerEvent::PlaybackStateChanged {
+                    sid: sid.to_owned(),
+                    state,
+                });
+            }
+
+            if let Some(uri) = value("CurrentTrackURI") {
+                events.push(SpeakerEvent::TrackChanged {
+                    sid: sid.to_owned(),
+                    uri: uri.to_owned(),
+                    metadata: value("CurrentTrackMetaData").unwrap_or_default().to_owned(),
+                });
+            }
+
+            let master_volume = parsed_change
+                .descendants()
+                .find(|node| {
+                    node.has_tag_name("Volume") && node.attribute("channel") == Some("Master")
+                })
+                .and_then(|node| node.attribute("val"));
+
+            if let Some(volume) = master_volume {
+                let volume = volume.parse::<u8>().map_err(|_| {
+                    XMLError::UnexpectedValue(format!("invalid volume: {}", volume))
+                })?;
+
+                events.push(SpeakerEvent::VolumeChanged {
+                    sid: sid.to_owned(),
+                    volume,
+                });
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(events)
+}
+
+pub(crate) fn parse_last_change_xml(last_change: &str) -> Result<Vec<TransportEvent>, XMLError> {
+    let last_change = decode_embedded_xml(last_change)?;
+
+    let parsed_change = roxmltree::Document::parse(&last_change)?;
+
+    let instance = get_tag_by_name(&parsed_change, "InstanceID")?;
+
+    Ok(instance
+        .children()
+        .filter(|node| node.is_element())
+        .filter_map(|node| {
+            node.attribute("val").map(|value| TransportEvent {
+                field: node.tag_name().name().to_owned(),
+                value: value.to_owned(),
+            })
+        })
+        .collect())
+}
+
+/// Parses XML embedded in a response as the text of a node, such as DIDL-Lite metadata or the zone group state, passing the parsed document to `parse`
+///
+/// The embedded XML is escaped, so it is only text in the outer document, and must be parsed separately once that document has unescaped it.
+/// Embedded XML that is URI-encoded instead, as `LastChange` sometimes is, is decoded first
+fn parse_escaped_xml<T, F>(node: Node, parse: F) -> Result<T, XMLError>
+where
+    F: FnOnce(&Document) -> Result<T, XMLError>,
+{
+    let text = decode_embedded_xml(&get_text(node)?)?;
+
+    let parsed_text = roxmltree::Document::parse(&text)?;
+
+    parse(&parsed_text)
+}
+
+/// Returns embedded XML, decoding it first if it is URI-encoded
+fn decode_embedded_xml(last_change: &str) -> Result<String, XMLError> {
+    let last_change = last_change.trim();
+
+    if last_change.starts_with('<') {
+        return Ok(last_change.to_owned());
+    }
+
+    let invalid_encoding =
+        || XMLError::UnexpectedValue(format!("invalid URI encoding: {}", last_change));
+
+    let mut decoded = Vec::with_capacity(last_change.len());
+    let mut bytes = last_change.bytes();
+
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'%' => {
+                let hex = [
+                    bytes.next().ok_or_else(invalid_encoding)?,
+                    bytes.next().ok_or_else(invalid_encoding)?,
+                ];
+                let hex = std::str::from_utf8(&hex).map_err(|_| invalid_encoding())?;
+
+                decoded.push(u8::from_str_radix(hex, 16).map_err(|_| invalid_encoding())?);
+            }
+            _ => decoded.push(byte),
+        }
+    }
+
+    String::from_utf8(decoded).map_err(|_| invalid_encoding())
+}
+
 pub(crate) fn get_error_code(xml: String) -> Result<String, XMLError> {
     let xml = clean_response_xml(xml);
 
@@ -182,30 +827,70 @@ pub(crate) fn get_error_code(xml: String) -> Result<String, XMLError> {
     get_text(get_tag_by_name(&parsed_xml, "errorCode")?)
 }
 
+const SONOS_MANUFACTURER: &str = "Sonos, Inc.";
+
 pub(crate) fn parse_description_xml(
     xml: String,
     ip_addr: Ipv4Addr,
 ) -> Result<BasicSpeakerInfo, XMLError> {
     let parsed_xml = roxmltree::Document::parse(&xml)?;
 
+    // other UPnP devices may answer the broadcast search request, so only Sonos devices are accepted
+    let manufacturer = get_text(get_tag_by_name(&parsed_xml, "manufacturer")?)?;
+
+    if manufacturer != SONOS_MANUFACTURER {
+        return Err(XMLError::UnexpectedValue(format!(
+            "manufacturer: {}",
+            manufacturer
+        )));
+    }
+
     let friendly_name = get_text(get_tag_by_name(&parsed_xml, "friendlyName")?)?;
 
     let room_name = get_text(get_tag_by_name(&parsed_xml, "roomName")?)?;
 
     let uuid = get_text(get_tag_by_name(&parsed_xml, "UDN")?)?.replace("uuid:", "");
 
+    let model_name = get_text(get_tag_by_name(&parsed_xml, "modelName")?)?;
+
+    let model_number = get_text(get_tag_by_name(&parsed_xml, "modelNumber")?)?;
+
+    let software_version = get_text(get_tag_by_name(&parsed_xml, "softwareVersion")?)?;
+
+    let hardware_version = get_text(get_tag_by_name(&parsed_xml, "hardwareVersion")?)?;
+
+    let household_id = get_tag_by_name(&parsed_xml, "householdId")
+        .ok()
+        .and_then(|node| node.text())
+        .map(str::to_string)
+        .unwrap_or_default();
+
     Ok(BasicSpeakerInfo {
         friendly_name,
         room_name,
         uuid,
+        model_name,
+        model_number,
+        software_version,
+        hardware_version,
+        household_id,
         ip_addr,
     })
 }
 
+// xml_builder does not escape text content, and argument values such as URI metadata may contain XML
+fn escape_text(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
 pub(crate) fn generate_xml(
     action_name: &str,
     service: &Service,
-    arguments: HashMap<&str, &str>,
+    arguments: &[(&str, &str)],
 ) -> Result<Vec<u8>, XMLError> {
     let mut xml = XMLBuilder::new()
         .version(XMLVersion::XML1_1)
@@ -227,9 +912,10 @@ pub(crate) fn generate_xml(
         &format!("urn:schemas-upnp-org:service:{}", service.get_name()),
     );
 
+    // arguments are written in the order given, since some actions expect them in the order they are declared
     for (arg, value) in arguments {
         let mut xml_obj = XMLElement::new(arg);
-        xml_obj.add_text(value.to_owned())?;
+        xml_obj.add_text(escape_text(value))?;
         action.add_child(xml_obj)?;
     }
 
@@ -243,3 +929,234 @@ pub(crate) fn generate_xml(
     xml.generate(&mut writer)?;
     Ok(writer)
 }
+
+pub(crate) fn generate_didl_xml(metadata: &DidlMetadata) -> Result<String, XMLError> {
+    let mut didl = XMLElement::new("DIDL-Lite");
+    didl.add_attribute("xmlns:dc", "http://purl.org/dc/elements/1.1/");
+    didl.add_attribute("xmlns:upnp", "urn:schemas-upnp-org:metadata-1-0/upnp/");
+    didl.add_attribute("xmlns:r", "urn:schemas-rinconnetworks-com:metadata-1-0/");
+    didl.add_attribute("xmlns", "urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/");
+
+    let mut item = XMLElement::new("item");
+    item.add_attribute("id", metadata.id.as_deref().unwrap_or("-1"));
+    item.add_attribute("parentID", "-1");
+    item.add_attribute("restricted", "true");
+
+    if let Some(uri) = &metadata.uri {
+        let mut res = XMLElement::new("res");
+        res.add_attribute("protocolInfo", "*:*:*:*");
+        if let Some(duration) = &metadata.duration {
+            res.add_attribute("duration", duration);
+        }
+        res.add_text(escape_text(uri))?;
+        item.add_child(res)?;
+    }
+
+    let fields = [
+        ("dc:title", &metadata.title),
+        ("dc:creator", &metadata.artist),
+        ("upnp:album", &metadata.album),
+        ("upnp:albumArtURI", &metadata.album_art_uri),
+    ];
+
+    for (tag_name, value) in fields {
+        if let Some(value) = value {
+            let mut element = XMLElement::new(tag_name);
+            element.add_text(escape_text(value))?;
+            item.add_child(element)?;
+        }
+    }
+
+    let mut class = XMLElement::new("upnp:class");
+    class.add_text(escape_text(
+        metadata.class.as_deref().unwrap_or(MUSIC_TRACK_CLASS),
+    ))?;
+    item.add_child(class)?;
+
+    didl.add_child(item)?;
+
+    let mut writer = Vec::new();
+    didl.render(&mut writer, false, false, false, false)?;
+
+    String::from_utf8(writer)
+        .map_err(|_| XMLError::UnexpectedValue(String::from("generated metadata is not UTF-8")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // wraps DIDL-Lite items in a ContentDirectory browse response, escaped as speakers send it
+    fn browse_response(items: &str, total_matches: u32) -> String {
+        let didl = format!(
+            r#"<DIDL-Lite xmlns:dc="http://purl.org/dc/elements/1.1/" xmlns:upnp="urn:schemas-upnp-org:metadata-1-0/upnp/" xmlns:r="urn:schemas-rinconnetworks-com:metadata-1-0/" xmlns="urn:schemas-upnp-org:metadata-1-0/DIDL-Lite/">{}</DIDL-Lite>"#,
+            items
+        );
+
+        format!(
+            r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/"><s:Body><u:BrowseResponse xmlns:u="urn:schemas-upnp-org:service:ContentDirectory:1"><Result>{}</Result><NumberReturned>{}</NumberReturned><TotalMatches>{}</TotalMatches><UpdateID>1</UpdateID></u:BrowseResponse></s:Body></s:Envelope>"#,
+            escape_text(&didl),
+            items.matches("</item>").count() + items.matches("</container>").count(),
+            total_matches
+        )
+    }
+
+    #[test]
+    fn parses_queue_item_with_all_elements() {
+        let xml = browse_response(
+            r#"<item id="Q:0/1" parentID="Q:0" restricted="true"><res protocolInfo="x-file-cifs:*:audio/mpeg:*" duration="0:03:45">x-file-cifs://nas/music/track.mp3</res><upnp:albumArtURI>/getaa?u=x-file-cifs%3a%2f%2fnas%2fmusic%2ftrack.mp3</upnp:albumArtURI><dc:title>Track Title</dc:title><upnp:class>object.item.audioItem.musicTrack</upnp:class><dc:creator>Track Artist</dc:creator><upnp:artist>Track Artist</upnp:artist><upnp:album>Album Title</upnp:album></item>"#,
+            1,
+        );
+
+        let (items, total_matches) = parse_queue_xml(xml).unwrap();
+
+        assert_eq!(total_matches, 1);
+        assert_eq!(items.len(), 1);
+
+        let item = &items[0];
+        assert_eq!(item.uri, "x-file-cifs://nas/music/track.mp3");
+        assert_eq!(item.duration.as_deref(), Some("0:03:45"));
+        assert_eq!(item.title.as_deref(), Some("Track Title"));
+        assert_eq!(item.artist.as_deref(), Some("Track Artist"));
+        assert_eq!(item.album.as_deref(), Some("Album Title"));
+        assert_eq!(
+            item.album_art_uri.as_deref(),
+            Some("/getaa?u=x-file-cifs%3a%2f%2fnas%2fmusic%2ftrack.mp3")
+        );
+    }
+
+    #[test]
+    fn parses_queue_item_without_album_elements() {
+        let xml = browse_response(
+            r#"<item id="Q:0/1" parentID="Q:0" restricted="true"><res protocolInfo="http-get:*:audio/mpeg:*">http://example.com/track.mp3</res><dc:title>Track Title</dc:title><upnp:class>object.item.audioItem.musicTrack</upnp:class><upnp:artist>Track Artist</upnp:artist></item>"#,
+            1,
+        );
+
+        let (items, _) = parse_queue_xml(xml).unwrap();
+
+        let item = &items[0];
+        assert_eq!(item.duration, None);
+        assert_eq!(item.album, None);
+        assert_eq!(item.album_art_uri, None);
+    }
+
+    fn crossfade_response(mode: &str) -> String {
+        format!(
+            r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/" s:encodingStyle="http://schemas.xmlsoap.org/soap/encoding/"><s:Body><u:GetCrossfadeModeResponse xmlns:u="urn:schemas-upnp-org:service:AVTransport:1"><CrossfadeMode>{}</CrossfadeMode></u:GetCrossfadeModeResponse></s:Body></s:Envelope>"#,
+            mode
+        )
+    }
+
+    #[test]
+    fn parses_browsed_items_and_containers() {
+        let metadata = r#"<DIDL-Lite><item id="F00092020s12345"><dc:title>Station</dc:title></item></DIDL-Lite>"#;
+
+        let xml = browse_response(
+            &format!(
+                r#"<item id="FV:2/3" parentID="FV:2" restricted="false"><dc:title>Station</dc:title><upnp:class>object.itemobject.item.sonos-favorite</upnp:class><upnp:albumArtURI>http://example.com/logo.png</upnp:albumArtURI><res protocolInfo="x-sonosapi-stream:*:*:*">x-sonosapi-stream:s12345?sid=254</res><r:resMD>{}</r:resMD></item><container id="SQ:3" parentID="SQ:" restricted="true" childCount="12"><dc:title>Playlist</dc:title><res protocolInfo="file:*:audio/mpegurl:*">file:///jffs/settings/savedqueues.rsq#3</res><upnp:class>object.container.playlistContainer</upnp:class></container>"#,
+                escape_text(metadata)
+            ),
+            2,
+        );
+
+        let result = parse_browse_xml(xml).unwrap();
+
+        assert_eq!(result.total_matches, 2);
+        assert_eq!(result.number_returned, 2);
+
+        let ContentNode::Item(item) = &result.items[0] else {
+            panic!("expected an item");
+        };
+        assert_eq!(item.id, "FV:2/3");
+        assert_eq!(item.title, "Station");
+        assert_eq!(item.uri, "x-sonosapi-stream:s12345?sid=254");
+        assert_eq!(item.metadata, metadata);
+        assert_eq!(item.art_uri.as_deref(), Some("http://example.com/logo.png"));
+
+        let ContentNode::Container(container) = &result.items[1] else {
+            panic!("expected a container");
+        };
+        assert_eq!(container.id, "SQ:3");
+        assert_eq!(container.title, "Playlist");
+        assert_eq!(container.uri, "file:///jffs/settings/savedqueues.rsq#3");
+        assert_eq!(container.child_count, 12);
+    }
+
+    const DESCRIPTION_XML: &str = r#"<?xml version="1.0" encoding="utf-8" ?>
+<root xmlns="urn:schemas-upnp-org:device-1-0">
+  <specVersion><major>1</major><minor>0</minor></specVersion>
+  <device>
+    <deviceType>urn:schemas-upnp-org:device:ZonePlayer:1</deviceType>
+    <friendlyName>192.168.1.10 - Sonos Play:5</friendlyName>
+    <manufacturer>Sonos, Inc.</manufacturer>
+    <manufacturerURL>http://www.sonos.com</manufacturerURL>
+    <modelNumber>S5</modelNumber>
+    <modelDescription>Sonos Play:5</modelDescription>
+    <modelName>Sonos Play:5</modelName>
+    <modelURL>http://www.sonos.com/products/zoneplayers/S5</modelURL>
+    <softwareVersion>79.1-56030</softwareVersion>
+    <hardwareVersion>1.16.4.1-2.0</hardwareVersion>
+    <serialNum>00-0E-58-A0-12-34:5</serialNum>
+    <UDN>uuid:RINCON_000E58A0123401400</UDN>
+    <roomName>Kitchen</roomName>
+    <householdId>Sonos_abcdefghijklmnop</householdId>
+  </device>
+</root>"#;
+
+    #[test]
+    fn parses_description() {
+        let ip_addr = Ipv4Addr::new(192, 168, 1, 10);
+
+        let info = parse_description_xml(DESCRIPTION_XML.to_owned(), ip_addr).unwrap();
+
+        assert_eq!(info.ip_addr(), ip_addr);
+        assert_eq!(info.friendly_name(), "192.168.1.10 - Sonos Play:5");
+        assert_eq!(info.room_name(), "Kitchen");
+        assert_eq!(info.uuid(), "RINCON_000E58A0123401400");
+        assert_eq!(info.model_name(), "Sonos Play:5");
+        assert_eq!(info.model_number(), "S5");
+        assert_eq!(info.software_version(), "79.1-56030");
+        assert_eq!(info.hardware_version(), "1.16.4.1-2.0");
+        assert_eq!(info.household_id(), "Sonos_abcdefghijklmnop");
+    }
+
+    #[test]
+    fn rejects_description_without_versions() {
+        let xml = DESCRIPTION_XML.replace("<softwareVersion>79.1-56030</softwareVersion>", "");
+
+        assert!(matches!(
+            parse_description_xml(xml, Ipv4Addr::LOCALHOST),
+            Err(XMLError::ElementNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn parses_crossfade_mode_enabled() {
+        assert!(parse_crossfade_mode_xml(crossfade_response("1")).unwrap());
+    }
+
+    #[test]
+    fn parses_crossfade_mode_disabled() {
+        assert!(!parse_crossfade_mode_xml(crossfade_response("0")).unwrap());
+    }
+
+    #[test]
+    fn rejects_invalid_crossfade_mode() {
+        assert!(matches!(
+            parse_crossfade_mode_xml(crossfade_response("true")),
+            Err(XMLError::UnexpectedValue(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_crossfade_response_without_mode() {
+        let xml = String::from(
+            r#"<s:Envelope xmlns:s="http://schemas.xmlsoap.org/soap/envelope/"><s:Body><u:GetCrossfadeModeResponse xmlns:u="urn:schemas-upnp-org:service:AVTransport:1"></u:GetCrossfadeModeResponse></s:Body></s:Envelope>"#,
+        );
+
+        assert!(matches!(
+            parse_crossfade_mode_xml(xml),
+            Err(XMLError::ElementNotFound(_))
+        ));
+    }
+}